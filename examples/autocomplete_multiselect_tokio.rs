@@ -0,0 +1,33 @@
+use prompts::{error::PromptError, multiselect::AutocompleteMultiSelectPrompt, Prompt};
+
+#[tokio::main]
+async fn main() {
+    let data = vec![
+        "serde",
+        "serde_json",
+        "serde_yaml",
+        "tokio",
+        "tokio-util",
+        "futures",
+        "rayon",
+        "clap",
+        "regex",
+        "rand",
+        "log",
+        "env_logger",
+    ];
+
+    // Prepare the prompt, case-insensitively matching anywhere in the crate name
+    let mut prompt = AutocompleteMultiSelectPrompt::new("Which crates do you need?", data)
+        .with_filter(|input, choice| choice.to_lowercase().contains(&input.to_lowercase()))
+        .with_min(1);
+
+    println!("Running prompt: {:?}", prompt);
+
+    // Run the prompt and echo the selection
+    match prompt.run().await {
+        Ok(crates) => println!("Adding: {}", crates.join(", ")),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
@@ -1,4 +1,4 @@
-use prompt::{autocomplete::AutocompletePrompt, Prompt};
+use prompts::{autocomplete::AutocompletePrompt, error::PromptError, Prompt};
 
 #[tokio::main]
 async fn main() {
@@ -14,8 +14,8 @@ async fn main() {
 
     // Run the prompt and echo the selection
     match prompt.run().await {
-        Ok(Some(s)) => println!("Your choice is: {}", s),
-        Ok(None) => println!("Prompt was aborted!"),
-        Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+        Ok(s) => println!("Your choice is: {}", s),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
     }
 }
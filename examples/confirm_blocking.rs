@@ -0,0 +1,16 @@
+use prompts::{confirm::ConfirmPrompt, error::PromptError, Prompt};
+
+fn main() {
+    // Prepare the prompt
+    let mut prompt = ConfirmPrompt::new("Are you sure?").set_initial(true);
+
+    println!("Running prompt: {:?}", prompt);
+
+    // Run the prompt without any async runtime and echo the result
+    match prompt.run_blocking() {
+        Ok(true) => println!("You were sure!"),
+        Ok(false) => println!("You were not sure!"),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
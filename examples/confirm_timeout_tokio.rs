@@ -0,0 +1,19 @@
+use prompts::{confirm::ConfirmPrompt, error::PromptError, Prompt};
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    // Prepare the prompt, which continues by itself after 10 seconds
+    let mut prompt = ConfirmPrompt::new("Continue deploying?")
+        .set_initial(true)
+        .with_timeout(Duration::from_secs(10))
+        .with_countdown();
+
+    // Run the prompt and echo the result
+    match prompt.run().await {
+        Ok(true) => println!("Deploying!"),
+        Ok(false) => println!("Deploy stopped."),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
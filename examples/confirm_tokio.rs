@@ -1,4 +1,4 @@
-use prompt::{confirm::ConfirmPrompt, Prompt};
+use prompts::{confirm::ConfirmPrompt, error::PromptError, Prompt};
 
 #[tokio::main]
 async fn main() {
@@ -9,9 +9,9 @@ async fn main() {
 
     // Run the prompt and echo the result
     match prompt.run().await {
-        Ok(Some(true)) => println!("You were sure!"),
-        Ok(Some(false)) => println!("You were not sure!"),
-        Ok(None) => println!("Prompt was aborted!"),
-        Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+        Ok(true) => println!("You were sure!"),
+        Ok(false) => println!("You were not sure!"),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
     }
 }
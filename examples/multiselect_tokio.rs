@@ -0,0 +1,26 @@
+use prompts::{error::PromptError, multiselect::MultiSelectPrompt, Prompt};
+
+#[tokio::main]
+async fn main() {
+    let data = vec![
+        "x86_64-unknown-linux-gnu",
+        "aarch64-unknown-linux-gnu",
+        "x86_64-apple-darwin",
+        "aarch64-apple-darwin",
+        "x86_64-pc-windows-msvc",
+        "wasm32-unknown-unknown",
+    ];
+
+    // Prepare the prompt, with the first target selected
+    let mut prompt =
+        MultiSelectPrompt::new("Which targets should we build?", data).set_selected(vec![0]);
+
+    println!("Running prompt: {:?}", prompt);
+
+    // Run the prompt and echo the selection
+    match prompt.run().await {
+        Ok(targets) => println!("Building for: {}", targets.join(", ")),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
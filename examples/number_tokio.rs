@@ -0,0 +1,21 @@
+use prompts::{error::PromptError, number::NumberPrompt, Prompt};
+
+#[tokio::main]
+async fn main() {
+    // Prepare the prompt
+    let mut prompt = NumberPrompt::new("How much should the discount be?")
+        .with_min(0.0)
+        .with_max(1.0)
+        .with_step(0.05)
+        .with_float(2)
+        .set_initial(0.1);
+
+    println!("Running prompt: {:?}", prompt);
+
+    // Run the prompt and echo the result
+    match prompt.run().await {
+        Ok(discount) => println!("Discount set to {}%", discount * 100.0),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
@@ -1,4 +1,4 @@
-use prompt::{select::SelectPrompt, Prompt};
+use prompts::{error::PromptError, select::SelectPrompt, Prompt};
 
 #[derive(Clone, Debug)] // Must derive Clone
 struct Person {
@@ -39,8 +39,8 @@ async fn main() {
 
     // Run the prompt and echo the chosen person's id
     match prompt.run().await {
-        Ok(Some(person)) => println!("That persons's id is: {}", person.id),
-        Ok(None) => println!("Prompt was aborted!"),
-        Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+        Ok(person) => println!("That persons's id is: {}", person.id),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
     }
 }
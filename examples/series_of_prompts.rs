@@ -1,30 +1,27 @@
-use prompt::{select::SelectPrompt, text::TextPrompt, Prompt};
+use prompts::{select::SelectPrompt, text::TextPrompt, Prompt};
 
 #[tokio::main]
 async fn main() {
     // Prompt for first name and store it in var
     let mut first_name_prompt = TextPrompt::new("What is your first name?");
     let first_name = match first_name_prompt.run().await {
-        Ok(Some(first_name)) => first_name,
-        Ok(None) => panic!("Prompt was aborted!"),
-        Err(e) => panic!("Some kind of crossterm error happened: {:?}", e),
+        Ok(first_name) => first_name,
+        Err(e) => panic!("Prompt failed: {}", e),
     };
 
     // Prompt for last name and store it in var
     let mut last_name_prompt = TextPrompt::new("What is your last name?");
     let last_name = match last_name_prompt.run().await {
-        Ok(Some(last_name)) => last_name,
-        Ok(None) => panic!("Prompt was aborted!"),
-        Err(e) => panic!("Some kind of crossterm error happened: {:?}", e),
+        Ok(last_name) => last_name,
+        Err(e) => panic!("Prompt failed: {}", e),
     };
 
     // Prompt for place and store it in var
     let places = vec!["The north", "The south", "The west", "The east"];
     let mut place_prompt = SelectPrompt::new("Where are you from?", places);
     let from = match place_prompt.run().await {
-        Ok(Some(from)) => from,
-        Ok(None) => panic!("Prompt was aborted!"),
-        Err(e) => panic!("Some kind of crossterm error happened: {:?}", e),
+        Ok(from) => from,
+        Err(e) => panic!("Prompt failed: {}", e),
     };
 
     // Echo the details we collected
@@ -0,0 +1,19 @@
+use prompts::{error::PromptError, text::TextPrompt, Prompt};
+use std::time::Duration;
+use tokio::time::delay_for;
+
+#[tokio::main]
+async fn main() {
+    // Prepare the prompt
+    let mut prompt = TextPrompt::new("Any last words before the build finishes?");
+
+    // Stand-in for a background job, the prompt is cancelled once it is done
+    let build = delay_for(Duration::from_secs(5));
+
+    // Run the prompt and echo the result
+    match prompt.run_until(build).await {
+        Ok(words) => println!("Noted: {}", words),
+        Err(PromptError::Cancelled) => println!("Build finished, never mind."),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
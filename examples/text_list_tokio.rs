@@ -0,0 +1,19 @@
+use prompts::{error::PromptError, text::ListPrompt, Prompt};
+
+#[tokio::main]
+async fn main() {
+    // Prepare the prompt, which splits on commas and checks every host name
+    let mut prompt = ListPrompt::new("Which hosts should we deploy to?")
+        .without_empty()
+        .with_validator(|host| match host.contains(char::is_whitespace) {
+            true => Err("Host names can't contain spaces".to_string()),
+            false => Ok(()),
+        });
+
+    // Run the prompt and echo the result
+    match prompt.run().await {
+        Ok(hosts) => println!("Deploying to {} host(s): {:?}", hosts.len(), hosts),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
@@ -0,0 +1,21 @@
+use prompts::{error::PromptError, text::ParsePrompt, Prompt};
+use std::net::IpAddr;
+
+#[tokio::main]
+async fn main() {
+    // Prepare the prompt, which only accepts valid IP addresses
+    let mut prompt =
+        ParsePrompt::<IpAddr>::new("Which address should we bind to?").with_validator(|address| {
+            match address.is_multicast() {
+                true => Err("Can't bind to a multicast address".to_string()),
+                false => Ok(()),
+            }
+        });
+
+    // Run the prompt and echo the result
+    match prompt.run().await {
+        Ok(address) => println!("Binding to {}", address),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
@@ -1,4 +1,4 @@
-use prompt::{text::TextPrompt, Prompt};
+use prompts::{error::PromptError, text::TextPrompt, Prompt};
 
 #[tokio::main]
 async fn main() {
@@ -9,8 +9,8 @@ async fn main() {
 
     // Run the prompt and echo the result
     match prompt.run().await {
-        Ok(Some(s)) => println!("You wrote: {}", s),
-        Ok(None) => println!("Prompt was aborted!"),
-        Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+        Ok(s) => println!("You wrote: {}", s),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
     }
 }
@@ -1,4 +1,5 @@
-use prompt::{
+use prompts::{
+    error::PromptError,
     text::{Style, TextPrompt},
     Prompt,
 };
@@ -18,8 +19,8 @@ async fn main() {
 
     // Run the prompt and echo the password
     match prompt.run().await {
-        Ok(Some(s)) => println!("You wrote: {}", s),
-        Ok(None) => println!("Prompt was aborted!"),
-        Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+        Ok(s) => println!("You wrote: {}", s),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
     }
 }
@@ -0,0 +1,19 @@
+use prompts::{error::PromptError, toggle::TogglePrompt, Prompt};
+
+#[tokio::main]
+async fn main() {
+    // Prepare the prompt, starting on staging
+    let mut prompt = TogglePrompt::new("Where should we deploy?")
+        .with_labels("production", "staging")
+        .set_initial(false);
+
+    println!("Running prompt: {:?}", prompt);
+
+    // Run the prompt and echo the answer
+    match prompt.run().await {
+        Ok(true) => println!("Deploying to production"),
+        Ok(false) => println!("Deploying to staging"),
+        Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+        Err(e) => println!("Prompt failed: {}", e),
+    }
+}
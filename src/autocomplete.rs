@@ -1,30 +1,24 @@
 //! Interactive prompt where the user chooses from a filterable list of options
 
 use crate::{
+    error::PromptError,
     utils::{
-        calc_entries, is_abort_event, print_input_icon, print_state_icon, Figures, PromptState,
+        find_choice, is_abort_event, print_input_icon, print_state_icon, Clicks, Frame, ListEntry,
+        ListView, Output, PromptCore, PromptState, TextInput,
     },
     Prompt,
 };
-use async_trait::async_trait;
 use crossterm::{
-    cursor,
-    event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
-    queue,
-    style::{style, Attribute, Color, Print, PrintStyledContent},
-    terminal::{disable_raw_mode, enable_raw_mode, size as terminal_size, Clear, ClearType},
+    event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent},
+    style::{style, Attribute, Color},
 };
-use futures::StreamExt;
 use std::cmp;
 use std::fmt;
-use std::io::{stdout, Write};
+use std::time::Duration;
 
 /// Default filter that simply filters all entires that start with the
 /// input based on the to_string of the object
-fn simple_filter<T: std::clone::Clone + std::fmt::Display>(
-    input: &str,
-    choices: &Vec<T>,
-) -> Vec<T> {
+fn simple_filter<T: std::clone::Clone + std::fmt::Display>(input: &str, choices: &[T]) -> Vec<T> {
     choices
         .iter()
         .filter(|choice| choice.to_string().starts_with(input))
@@ -32,37 +26,44 @@ fn simple_filter<T: std::clone::Clone + std::fmt::Display>(
         .collect()
 }
 
+/// Filter function that returns the choices matching the input
+type Filter<T> = Box<dyn Fn(&str, &[T]) -> Vec<T> + Send>;
+
 /// Interactive prompt where the user chooses from a list of options
 ///
 /// Shows a list of options. Use <kbd>up</kbd>/<kbd>down</kbd> to navigate
 /// and <kbd>enter</kbd> to submit. Type anything to filter the list.
-/// The default filter will simply check the choices start with the input's .to_string().
+/// The default filter will simply check the choices start with the input's .to_string(),
+/// use `with_filter` to filter differently.
 /// The data vector can have a custom type but it must implement
 /// `std::fmt::Display` as well as `std::clone::Clone` and `std::marker::Send`.
 ///
 /// # Examples
 ///
 /// ```
-/// use prompt::{Prompt, autocomplete::{AutocompletePrompt}};
+/// use prompts::{error::PromptError, Prompt, autocomplete::{AutocompletePrompt}};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
 ///
 /// let data = vec!["The", "quick", "brown", "fox", "jumps", "over", "the", "lazy", "dog"];
 /// let mut prompt = AutocompletePrompt::new("Choose a word", data);
 ///
 /// match prompt.run().await {
-///     Ok(Some(s)) => println!("Your choice is: {}", s),
-///     Ok(None) => println!("Prompt was aborted!"),
-///     Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+///     Ok(s) => println!("Your choice is: {}", s),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
 /// }
+/// # }
 /// ```
 pub struct AutocompletePrompt<T: std::clone::Clone + std::marker::Send + std::fmt::Display> {
     message: String,
-    state: PromptState,
+    core: PromptCore,
     choices: Vec<T>,
     current: usize,
-    limit: usize,
-    input: String,
-    cursor: usize,
-    filter: fn(input: &str, choices: &Vec<T>) -> Vec<T>,
+    list: ListView,
+    clicks: Clicks,
+    input: TextInput,
+    filter: Option<Filter<T>>,
 }
 impl<T: std::fmt::Debug + std::clone::Clone + std::marker::Send + std::fmt::Display> fmt::Debug
     for AutocompletePrompt<T>
@@ -71,7 +72,16 @@ impl<T: std::fmt::Debug + std::clone::Clone + std::marker::Send + std::fmt::Disp
         fmt.debug_struct("AutocompletePrompt")
             .field("message", &self.message)
             .field("choices", &self.choices)
-            .field("filter", &format_args!("{}", "unknown filter"))
+            .field(
+                "filter",
+                &format_args!(
+                    "{}",
+                    &match self.filter {
+                        Some(_) => "custom filter",
+                        None => "simple filter",
+                    }
+                ),
+            )
             .finish()
     }
 }
@@ -89,152 +99,150 @@ impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> AutocompleteP
         AutocompletePrompt {
             message: message.into(),
             choices,
-            state: PromptState::default(),
+            core: PromptCore::default(),
             current: 0,
-            limit: 10,
-            input: "".to_string(),
-            cursor: 0,
-            filter: simple_filter,
+            list: ListView::new(10),
+            clicks: Clicks::default(),
+            input: TextInput::default(),
+            filter: None,
         }
     }
-}
-#[async_trait]
-impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<T>
-    for AutocompletePrompt<T>
-{
-    /// Runs the prompt
+
+    /// Provide a custom filter closure
+    ///
+    /// # Arguments
     ///
-    /// Stops either when the user selects an option, an error occurs,
-    /// or the prompt is aborted by the user using CTRL+c, CTRL+z or ESC.
-    async fn run(&mut self) -> std::result::Result<Option<T>, crossterm::ErrorKind> {
-        enable_raw_mode()?;
-        let mut reader = EventStream::new();
+    /// * `filter` - Filter closure that accepts the input and all choices,
+    ///   and returns the choices that match the input, in the order to show them.
+    pub fn with_filter<F>(mut self, filter: F) -> AutocompletePrompt<T>
+    where
+        F: Fn(&str, &[T]) -> Vec<T> + Send + 'static,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
 
-        self.display()?;
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> AutocompletePrompt<T> {
+        self.core.output = output;
+        self
+    }
 
-        loop {
-            match reader.next().await {
-                Some(Ok(Event::Key(event))) => self.handle_key_event(event),
-                Some(Err(e)) => {
-                    disable_raw_mode()?;
-                    return Err(e);
-                }
-                _ => {}
-            }
+    /// Give up waiting for an answer after some time
+    ///
+    /// The highlighted match is not submitted, running the prompt
+    /// fails with `PromptError::Timeout` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> AutocompletePrompt<T> {
+        self.core.timeout = Some(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> AutocompletePrompt<T> {
+        self.core.countdown = true;
+        self
+    }
 
-            self.display()?;
+    /// Capture the mouse, so choices can be clicked and scrolled through
+    ///
+    /// Click a choice to highlight it, double-click to submit it.
+    pub fn with_mouse(mut self) -> AutocompletePrompt<T> {
+        self.core.mouse = true;
+        self
+    }
 
-            match self.state {
-                PromptState::Aborted => {
-                    disable_raw_mode()?;
-                    return Ok(None);
-                }
-                PromptState::Success => {
-                    disable_raw_mode()?;
-                    let filtered_choices = (self.filter)(&self.input, &self.choices);
-                    return Ok(Some(filtered_choices[self.current].clone()));
-                }
-                _ => (),
-            }
+    /// Returns the choices that match the input
+    fn filtered_choices(&self) -> Vec<T> {
+        match &self.filter {
+            Some(filter) => filter(self.input.as_str(), &self.choices),
+            None => simple_filter(self.input.as_str(), &self.choices),
+        }
+    }
+}
+impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<T>
+    for AutocompletePrompt<T>
+{
+    fn line_question(&self) -> Option<String> {
+        Some(self.message.clone())
+    }
+    /// Accepts the text of a choice, or its (zero-based) index
+    fn parse_line(&mut self, line: &str) -> std::result::Result<T, String> {
+        let index = find_choice(&self.choices, line)?;
+        Ok(self.choices[index].clone())
+    }
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        if self.choices.is_empty() {
+            return Err(PromptError::InvalidConfiguration(
+                "there are no choices to choose from".to_string(),
+            ));
         }
+        Ok(())
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    fn result(&self) -> T {
+        self.filtered_choices()[self.current].clone()
     }
     fn display(&mut self) -> crossterm::Result<()> {
-        let mut stdout = stdout();
-
-        let filtered_choices = (self.filter)(&self.input, &self.choices);
+        let filtered_choices = self.filtered_choices();
 
-        self.current = cmp::min(
-            self.current,
-            filtered_choices.len().checked_sub(1).unwrap_or(0),
-        );
+        self.current = cmp::min(self.current, filtered_choices.len().saturating_sub(1));
 
-        let (start_index, end_index) = calc_entries(
-            self.current,
-            filtered_choices.len(),
-            cmp::min(self.limit, (terminal_size()?.1 - 1) as usize),
-        );
-
-        if self.state == PromptState::Created {
-            self.state = PromptState::Running;
-        } else {
-            queue!(
-                stdout,
-                cursor::MoveUp((end_index - start_index) as u16),
-                cursor::MoveToColumn(0),
-                Clear(ClearType::FromCursorDown)
-            )?;
+        if self.core.state == PromptState::Created {
+            self.core.state = PromptState::Running;
         }
 
-        queue!(
-            stdout,
-            print_state_icon(&self.state),
-            Print(" "),
-            PrintStyledContent(style(&self.message).attribute(Attribute::Bold))
-        )?;
-        if !self.state.is_done() {
-            let input_column = (2 + self.message.len() + 3 + self.cursor + 1) as u16;
-
-            queue!(
-                stdout,
-                Print(" "),
-                print_input_icon(&self.state),
-                Print(&self.input),
-                cursor::SavePosition
-            )?;
-            if start_index == end_index {
-                queue!(
-                    stdout,
-                    Print("\n\r"),
-                    PrintStyledContent(style("Nothing matched your search").with(Color::DarkGrey)),
-                )?;
-            } else {
-                for i in start_index..end_index {
-                    let choice = filtered_choices[i].to_string();
-                    let prefix = if i == start_index && start_index > 0 {
-                        Figures::ArrowUp.as_str()
-                    } else if i == end_index - 1 && end_index < filtered_choices.len() {
-                        Figures::ArrowDown.as_str()
-                    } else {
-                        " "
-                    };
-                    queue!(
-                        stdout,
-                        Print("\n\r"),
-                        PrintStyledContent(if i == self.current {
-                            style(Figures::Pointer.as_str()).with(Color::Cyan)
-                        } else {
-                            style(" ")
-                        }),
-                        Print(format!(" {} ", prefix)),
-                        PrintStyledContent(if i == self.current {
-                            style(choice).attribute(Attribute::Bold).with(Color::Cyan)
-                        } else {
-                            style(choice)
-                        }),
-                    )?;
-                }
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        if !self.core.state.is_done() {
+            frame.print(" ");
+            frame.print_styled(print_input_icon(&self.core.state).0);
+            frame.print(self.input.before_cursor());
+            frame.set_cursor();
+            frame.print(self.input.after_cursor());
+            if filtered_choices.is_empty() {
+                frame.new_line();
+                frame.print_styled(style("Nothing matched your search").with(Color::DarkGrey));
             }
-
-            queue!(
-                stdout,
-                cursor::RestorePosition,
-                cursor::MoveToColumn(input_column)
-            )?;
-        }
-        if self.state.is_done() {
-            queue!(stdout, Print("\n\r"))?;
+            self.list.print(
+                &mut frame,
+                self.core.output.size(),
+                0,
+                filtered_choices.len(),
+                self.current,
+                |i| ListEntry {
+                    marker: None,
+                    text: filtered_choices[i].to_string(),
+                },
+            );
+        } else {
+            frame.new_line();
         }
-        stdout.flush()?;
-        crossterm::Result::Ok(())
+        self.core.render(&frame)
     }
     fn handle_key_event(&mut self, event: KeyEvent) {
         if is_abort_event(event) {
-            self.state = PromptState::Aborted;
+            self.core.state = PromptState::Aborted;
             return;
         }
         if event.modifiers == KeyModifiers::empty() {
             match event.code {
-                KeyCode::Enter => self.state = PromptState::Success,
+                // Nothing to submit if the filter matched nothing
+                KeyCode::Enter if !self.filtered_choices().is_empty() => {
+                    self.core.state = PromptState::Success
+                }
                 KeyCode::Home => {
                     self.current = 0;
                 }
@@ -242,29 +250,88 @@ impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<T>
                     self.current = self.choices.len() - 1;
                 }
                 KeyCode::Up => {
-                    self.current = self.current.checked_sub(1).unwrap_or(0);
+                    self.current = self.current.saturating_sub(1);
                 }
                 KeyCode::Down => {
                     self.current = cmp::min(self.current + 1, self.choices.len() - 1);
                 }
-                KeyCode::Backspace => {
-                    self.cursor = self.cursor.checked_sub(1).unwrap_or(0);
-                    if self.input.len() > self.cursor {
-                        self.input.remove(self.cursor);
+                KeyCode::Backspace => self.input.backspace(),
+                KeyCode::Delete => self.input.delete(),
+                KeyCode::Left => self.input.move_left(),
+                KeyCode::Right => self.input.move_right(),
+                KeyCode::Char(c) => self.input.insert(c),
+                _ => {}
+            }
+        }
+    }
+    fn handle_mouse_event(&mut self, event: MouseEvent) {
+        let matches = self.filtered_choices().len();
+        match event {
+            MouseEvent::Down(MouseButton::Left, _, row, _) => {
+                let line = self.core.line_at(row);
+                if let Some(index) = line.and_then(|line| self.list.clicked_entry(line)) {
+                    self.current = index;
+                    if self.clicks.click(index) {
+                        self.core.state = PromptState::Success;
                     }
                 }
-                KeyCode::Left => {
-                    self.cursor = self.cursor.checked_sub(1).unwrap_or(0);
-                }
-                KeyCode::Right => {
-                    self.cursor = cmp::min(self.cursor + 1, self.input.len());
-                }
-                KeyCode::Char(c) => {
-                    self.input.insert(self.cursor, c);
-                    self.cursor += 1;
-                }
-                _ => {}
             }
+            MouseEvent::ScrollUp(..) => {
+                self.current = self.current.saturating_sub(1);
+            }
+            MouseEvent::ScrollDown(..) => {
+                self.current = cmp::min(self.current + 1, matches.saturating_sub(1));
+            }
+            _ => {}
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::{key_events, typed};
+    use crossterm::event::Event;
+    use std::io::sink;
+
+    #[test]
+    fn run_with_custom_filter() {
+        let ignore_case = true;
+        let mut prompt = AutocompletePrompt::new("Choose a word", vec!["The", "quick", "the"])
+            .with_filter(move |input, choices| {
+                choices
+                    .iter()
+                    .filter(|choice| match ignore_case {
+                        true => choice.to_lowercase().contains(&input.to_lowercase()),
+                        false => choice.contains(input),
+                    })
+                    .cloned()
+                    .collect()
+            })
+            .with_output(Output::new(sink()));
+        let mut events = typed("HE");
+        events.push(KeyEvent::from(KeyCode::Down));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), "the");
+    }
+
+    #[test]
+    fn run_with_mouse_on_filtered_choices() {
+        let mut prompt =
+            AutocompletePrompt::new("Choose a word", vec!["The", "quick", "brown", "bar"])
+                .with_output(Output::new(sink()))
+                .with_mouse();
+        let click = || {
+            Ok(Event::Mouse(MouseEvent::Down(
+                MouseButton::Left,
+                4,
+                2,
+                KeyModifiers::empty(),
+            )))
+        };
+        let events = vec![Ok(Event::Key(KeyCode::Char('b').into())), click(), click()];
+        let result = prompt.run_blocking_with_events(events.into_iter());
+        assert_eq!(result.unwrap(), "bar");
+    }
+}
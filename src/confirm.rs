@@ -1,43 +1,47 @@
 //! Interactive prompt where the user can choose yes or no
 
 use crate::{
-    utils::{is_abort_event, print_input_icon, print_state_icon, PromptState},
+    utils::{
+        is_abort_event, print_input_icon, print_state_icon, Frame, Output, PromptCore, PromptState,
+    },
     Prompt,
 };
-use async_trait::async_trait;
 use crossterm::{
     cursor,
-    event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
+    event::{KeyCode, KeyEvent, KeyModifiers},
     queue,
-    style::{style, Attribute, Color, Print, PrintStyledContent},
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    style::{style, Attribute, Color},
 };
-use futures::StreamExt;
 use std::fmt;
-use std::io::{stdout, Write};
+use std::io::Write;
+use std::time::Duration;
 
 /// Interactive prompt where the user can choose yes or no
 ///
 /// Use <kbd>y</kbd>/<kbd>n</kbd> to answer the prompt.
-/// If default/initial is set <kbd>enter</kbd> will submit that value.
+/// If default/initial is set <kbd>enter</kbd> will submit that value,
+/// as will running out of time if a timeout is set.
 ///
 /// # Examples
 ///
 /// ```
-/// use prompt::{confirm::ConfirmPrompt, Prompt};
+/// use prompts::{confirm::ConfirmPrompt, error::PromptError, Prompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
 /// let mut prompt = ConfirmPrompt::new("Are you sure?");
 ///
 /// match prompt.run().await {
-///     Ok(Some(true)) => println!("You were sure!"),
-///     Ok(Some(false)) => println!("You were not sure!"),
-///     Ok(None) => println!("Prompt was aborted!"),
-///     Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+///     Ok(true) => println!("You were sure!"),
+///     Ok(false) => println!("You were not sure!"),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
 /// }
+/// # }
 /// ```
 #[derive(Default)]
 pub struct ConfirmPrompt {
     message: String,
-    state: PromptState,
+    core: PromptCore,
     answer: bool,
     initial: Option<bool>,
 }
@@ -70,82 +74,100 @@ impl ConfirmPrompt {
         self.initial = Some(initial);
         self
     }
-}
-#[async_trait]
-impl Prompt<bool> for ConfirmPrompt {
-    /// Runs the prompt
-    ///
-    /// Stops either when the user selects an option, an error occurs,
-    /// or the prompt is aborted by the user using CTRL+c, CTRL+z or ESC.
-    async fn run(&mut self) -> std::result::Result<Option<bool>, crossterm::ErrorKind> {
-        enable_raw_mode()?;
-        let mut reader = EventStream::new();
 
-        self.display()?;
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> ConfirmPrompt {
+        self.core.output = output;
+        self
+    }
 
-        loop {
-            match reader.next().await {
-                Some(Ok(Event::Key(event))) => self.handle_key_event(event),
-                Some(Err(e)) => {
-                    disable_raw_mode()?;
-                    return Err(e);
-                }
-                _ => {}
-            }
+    /// Give up waiting for an answer after some time
+    ///
+    /// When it expires the initial answer is submitted, see `set_initial`.
+    /// Without one, running the prompt fails with `PromptError::Timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> ConfirmPrompt {
+        self.core.timeout = Some(timeout);
+        self
+    }
 
-            self.display()?;
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> ConfirmPrompt {
+        self.core.countdown = true;
+        self
+    }
 
-            match self.state {
-                PromptState::Aborted => {
-                    disable_raw_mode()?;
-                    return Ok(None);
-                }
-                PromptState::Success => {
-                    disable_raw_mode()?;
-                    return Ok(Some(self.answer));
-                }
-                _ => (),
+    /// Shows which answers are possible, and which one is the default
+    fn hint(&self) -> &'static str {
+        match self.initial {
+            Some(true) => "(Y/n)",
+            Some(false) => "(y/N)",
+            None => "(y/n)",
+        }
+    }
+}
+impl Prompt<bool> for ConfirmPrompt {
+    fn line_question(&self) -> Option<String> {
+        Some(format!("{} {}", self.message, self.hint()))
+    }
+    /// Accepts y/yes or n/no, or an empty line if there is a default answer
+    fn parse_line(&mut self, line: &str) -> std::result::Result<bool, String> {
+        self.answer = match line.trim().to_lowercase().as_str() {
+            "y" | "yes" => true,
+            "n" | "no" => false,
+            "" => self
+                .initial
+                .ok_or_else(|| "Please answer y or n".to_string())?,
+            _ => return Err("Please answer y or n".to_string()),
+        };
+        Ok(self.answer)
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    /// Submits the initial answer, if there is one
+    fn submit_default(&mut self) -> bool {
+        match self.initial {
+            Some(initial) => {
+                self.answer = initial;
+                true
             }
+            None => false,
         }
     }
+    fn result(&self) -> bool {
+        self.answer
+    }
     fn display(&mut self) -> crossterm::Result<()> {
-        let mut stdout = stdout();
-
-        queue!(
-            stdout,
-            cursor::MoveToColumn(0),
-            Clear(ClearType::FromCursorDown),
-            print_state_icon(&self.state),
-            Print(" "),
-            PrintStyledContent(style(&self.message).attribute(Attribute::Bold)),
-            Print(" "),
-            print_input_icon(&self.state),
-        )?;
-        if !self.state.is_done() {
-            queue!(
-                stdout,
-                PrintStyledContent(
-                    style(match self.initial {
-                        Some(true) => "(Y/n)",
-                        Some(false) => "(y/N)",
-                        None => "(y/n)",
-                    })
-                    .with(Color::DarkGrey)
-                )
-            )?;
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        frame.print(" ");
+        frame.print_styled(print_input_icon(&self.core.state).0);
+        if !self.core.state.is_done() {
+            frame.print_styled(style(self.hint()).with(Color::DarkGrey));
         }
-        if self.state == PromptState::Success {
-            queue!(stdout, Print(if self.answer { "yes" } else { "no" }))?;
+        if self.core.state == PromptState::Success {
+            frame.print(if self.answer { "yes" } else { "no" });
         }
-        if self.state.is_done() {
-            queue!(stdout, Print("\n\r"), cursor::Show)?;
+        if self.core.state.is_done() {
+            frame.new_line();
+            queue!(self.core.output, cursor::Show)?;
         }
-        stdout.flush()?;
-        crossterm::Result::Ok(())
+        self.core.render(&frame)
     }
     fn handle_key_event(&mut self, event: KeyEvent) {
         if is_abort_event(event) {
-            self.state = PromptState::Aborted;
+            self.core.state = PromptState::Aborted;
             return;
         }
         if event.modifiers == KeyModifiers::empty() {
@@ -153,19 +175,70 @@ impl Prompt<bool> for ConfirmPrompt {
                 KeyCode::Enter => {
                     if let Some(initial) = self.initial {
                         self.answer = initial;
-                        self.state = PromptState::Success;
+                        self.core.state = PromptState::Success;
                     }
                 }
                 KeyCode::Char('y') => {
                     self.answer = true;
-                    self.state = PromptState::Success;
+                    self.core.state = PromptState::Success;
                 }
                 KeyCode::Char('n') => {
                     self.answer = false;
-                    self.state = PromptState::Success;
+                    self.core.state = PromptState::Success;
                 }
                 _ => {}
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PromptError;
+    #[cfg(feature = "async")]
+    use futures::{executor::block_on, stream};
+    use std::io::sink;
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run_submits_initial_on_timeout() {
+        let mut prompt = ConfirmPrompt::new("Deploy?")
+            .set_initial(true)
+            .with_timeout(Duration::from_millis(10))
+            .with_output(Output::new(sink()));
+        let result = block_on(prompt.run_with_events(stream::pending()));
+        assert!(result.unwrap());
+    }
+
+    #[test]
+    fn run_lines_parses_answer() {
+        let mut prompt = ConfirmPrompt::new("Sure?").with_output(Output::new(sink()));
+        assert!(prompt.run_lines("Yes\n".as_bytes()).unwrap());
+        let mut prompt = ConfirmPrompt::new("Sure?").with_output(Output::new(sink()));
+        assert!(!prompt.run_lines("n\n".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn run_lines_asks_again() {
+        let mut prompt = ConfirmPrompt::new("Sure?").with_output(Output::new(sink()));
+        assert!(prompt.run_lines("\nmaybe\ny\n".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn run_lines_uses_initial() {
+        let mut prompt = ConfirmPrompt::new("Sure?")
+            .set_initial(true)
+            .with_output(Output::new(sink()));
+        assert!(prompt.run_lines("\n".as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn run_lines_eof() {
+        let mut prompt = ConfirmPrompt::new("Sure?").with_output(Output::new(sink()));
+        match prompt.run_lines("".as_bytes()) {
+            Err(PromptError::Eof) => {}
+            _ => panic!("expected an Eof error"),
+        }
+    }
+}
@@ -0,0 +1,1035 @@
+//! Event loop shared by all prompts
+//!
+//! Reads events, hands key presses to the prompt, validates the answer
+//! when the prompt asks for it and renders after every event, until the
+//! prompt is answered or aborted. A resize clears the screen and renders
+//! the prompt again from the top, laid out for the new size.
+//!
+//! If the prompt has a timeout, the driver also wakes up when it expires,
+//! and every second in between if a countdown is shown.
+//!
+//! Validations running in the background are waited for next to events,
+//! with key presses other than aborting ones ignored in the meantime.
+//!
+//! A prompt whose future is dropped before it is done, e.g. because it was
+//! cancelled, is shown as aborted before the terminal is restored.
+//!
+//! Without a terminal, the answer is read line by line instead. Lines of
+//! stdin are read on a thread of their own, so waiting for one doesn't
+//! block the async runner and can end when the timeout expires.
+
+use crate::{
+    error::PromptError,
+    utils::{abort_error, Figures, PromptCore, PromptState, TerminalGuard},
+    Prompt,
+};
+#[cfg(feature = "async")]
+use crossterm::event::EventStream;
+use crossterm::{
+    cursor,
+    event::{poll, read, Event},
+    queue,
+    terminal::{Clear, ClearType},
+};
+#[cfg(feature = "async")]
+use futures::executor::block_on;
+#[cfg(feature = "async")]
+use futures::{
+    future::{self, Either},
+    pin_mut,
+    task::{Poll, Waker},
+    Future, FutureExt, Stream, StreamExt,
+};
+#[cfg(feature = "async")]
+use futures_timer::Delay;
+use std::cmp;
+use std::io::{self, stdin, BufRead, IsTerminal, Write};
+use std::marker::PhantomData;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Runs the prompt in the terminal, or line by line if stdin is not a terminal
+#[cfg(feature = "async")]
+pub async fn run<P, T>(prompt: &mut P) -> Result<T, PromptError>
+where
+    P: Prompt<T> + Send + ?Sized,
+    T: Send,
+{
+    if !stdin().is_terminal() {
+        return run_lines_async(prompt, &Lines::stdin()).await;
+    }
+    prompt.check()?;
+    let terminal = start_terminal(prompt)?;
+    let session = Session::new(prompt, Some(terminal));
+    let result = run_events(session.prompt, EventStream::new()).await;
+    session.finish(result)
+}
+
+/// Runs the prompt in the terminal until it is done or `cancel` completes
+#[cfg(feature = "async")]
+pub async fn run_until<P, T, F>(prompt: &mut P, cancel: F) -> Result<T, PromptError>
+where
+    P: Prompt<T> + Send + ?Sized,
+    T: Send,
+    F: Future<Output = ()> + Send,
+{
+    pin_mut!(cancel);
+    match future::select(prompt.run(), cancel).await {
+        Either::Left((result, _)) => result,
+        // Dropping the future of the prompt shows it as aborted and restores the terminal
+        Either::Right(_) => Err(PromptError::Cancelled),
+    }
+}
+
+/// Runs the prompt until it is done, reading events from the stream
+#[cfg(feature = "async")]
+pub async fn run_with_events<P, T, S>(prompt: &mut P, events: S) -> Result<T, PromptError>
+where
+    P: Prompt<T> + Send + ?Sized,
+    S: Stream<Item = crossterm::Result<Event>> + Unpin + Send,
+{
+    prompt.check()?;
+    let session = Session::new(prompt, None);
+    let result = run_events(session.prompt, events).await;
+    session.finish(result)
+}
+
+/// Reads events from the stream until the prompt is done
+#[cfg(feature = "async")]
+async fn run_events<P, T, S>(prompt: &mut P, mut events: S) -> Result<T, PromptError>
+where
+    P: Prompt<T> + Send + ?Sized,
+    S: Stream<Item = crossterm::Result<Event>> + Unpin + Send,
+{
+    start(prompt)?;
+    loop {
+        let answer = match wake(prompt.core(), &mut events).await {
+            Wake::Event(event) => handle_event(prompt, event)?,
+            Wake::Tick => handle_tick(prompt)?,
+            Wake::Validated(result) => {
+                let core = prompt.core();
+                core.validation = None;
+                apply_validation(core, result);
+                prompt.display()?;
+                answer(prompt)
+            }
+        };
+        if let Some(answer) = answer {
+            return Ok(answer);
+        }
+    }
+}
+
+/// What the driver woke up for
+#[cfg(feature = "async")]
+enum Wake {
+    Event(Option<crossterm::Result<Event>>),
+    Tick,
+    Validated(Result<(), String>),
+}
+
+/// Waits for the next event, timer tick or the background validation to complete
+#[cfg(feature = "async")]
+async fn wake<S>(core: &mut PromptCore, events: &mut S) -> Wake
+where
+    S: Stream<Item = crossterm::Result<Event>> + Unpin + Send,
+{
+    let event = events.next().map(Wake::Event);
+    let tick = match next_tick(core) {
+        Some(wait) => Delay::new(wait).map(|_| Wake::Tick).left_future(),
+        None => future::pending().right_future(),
+    };
+    let validated = match &mut core.validation {
+        Some(validation) => validation.map(Wake::Validated).left_future(),
+        None => future::pending().right_future(),
+    };
+    match future::select(event, future::select(tick, validated)).await {
+        Either::Left((wake, _))
+        | Either::Right((Either::Left((wake, _)), _))
+        | Either::Right((Either::Right((wake, _)), _)) => wake,
+    }
+}
+
+/// Runs the prompt in the terminal, blocking the current thread
+pub fn run_blocking<P, T>(prompt: &mut P) -> Result<T, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    if !stdin().is_terminal() {
+        return run_lines_blocking(prompt, &Lines::stdin());
+    }
+    prompt.check()?;
+    let terminal = start_terminal(prompt)?;
+    let session = Session::new(prompt, Some(terminal));
+    let result = run_blocking_terminal(session.prompt);
+    session.finish(result)
+}
+
+/// Runs the prompt until it is done, reading events from the terminal
+fn run_blocking_terminal<P, T>(prompt: &mut P) -> Result<T, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    start(prompt)?;
+    loop {
+        if let Some(wait) = next_tick(prompt.core()) {
+            if !poll(wait)? {
+                if let Some(answer) = handle_tick(prompt)? {
+                    return Ok(answer);
+                }
+                continue;
+            }
+        }
+        if let Some(answer) = handle_event(prompt, Some(read()))? {
+            return Ok(answer);
+        }
+        if let Some(answer) = wait_for_validation(prompt)? {
+            return Ok(answer);
+        }
+    }
+}
+
+/// Runs the prompt until it is done, reading events from the iterator
+///
+/// An iterator can't be waited on with a timeout, so an expired timeout
+/// is only noticed when the next event arrives.
+pub fn run_blocking_with_events<P, T, I>(prompt: &mut P, mut events: I) -> Result<T, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+    I: Iterator<Item = crossterm::Result<Event>>,
+{
+    prompt.check()?;
+    start(prompt)?;
+    loop {
+        let event = events.next();
+        if expired(prompt.core()) {
+            return time_out(prompt);
+        }
+        if let Some(answer) = handle_event(prompt, event)? {
+            return Ok(answer);
+        }
+        if let Some(answer) = wait_for_validation(prompt)? {
+            return Ok(answer);
+        }
+    }
+}
+
+/// Runs the prompt without a terminal, reading the answer line by line from the input
+///
+/// Reading from the input can't be interrupted, so an expired timeout
+/// is only noticed when the next line arrives.
+pub fn run_lines<P, T, R>(prompt: &mut P, mut input: R) -> Result<T, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+    R: BufRead,
+{
+    start_lines(prompt)?;
+    loop {
+        ask(prompt)?;
+        let mut line = String::new();
+        let read = input.read_line(&mut line)?;
+        if expired(prompt.core()) {
+            return time_out_lines(prompt);
+        }
+        if let Some(answer) = answer_line_blocking(prompt, Some(line).filter(|_| read > 0))? {
+            return Ok(answer);
+        }
+    }
+}
+
+/// Runs the prompt without a terminal, awaiting the answer line by line
+#[cfg(feature = "async")]
+async fn run_lines_async<P, T>(prompt: &mut P, lines: &Lines) -> Result<T, PromptError>
+where
+    P: Prompt<T> + Send + ?Sized,
+    T: Send,
+{
+    start_lines(prompt)?;
+    loop {
+        ask(prompt)?;
+        let line = lines.next();
+        pin_mut!(line);
+        let line = match future::select(line, expiry(prompt.core())).await {
+            Either::Left((line, _)) => line?,
+            Either::Right(_) => return time_out_lines(prompt),
+        };
+        let answer = match parse_line(prompt, line)? {
+            Some(answer) => answer,
+            None => continue,
+        };
+        if let Some(validation) = prompt.validate_async() {
+            match future::select(validation, expiry(prompt.core())).await {
+                Either::Left((Ok(()), _)) => {}
+                Either::Left((Err(msg), _)) => {
+                    print_line_error(prompt, &msg)?;
+                    continue;
+                }
+                Either::Right(_) => return time_out_lines(prompt),
+            }
+        }
+        prompt.core().state = PromptState::Success;
+        return Ok(answer);
+    }
+}
+
+/// Runs the prompt without a terminal, waiting for the answer line by line
+fn run_lines_blocking<P, T>(prompt: &mut P, lines: &Lines) -> Result<T, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    start_lines(prompt)?;
+    loop {
+        ask(prompt)?;
+        let line = match lines.next_blocking(prompt.core().deadline) {
+            Some(line) => line?,
+            None => return time_out_lines(prompt),
+        };
+        if let Some(answer) = answer_line_blocking(prompt, line)? {
+            return Ok(answer);
+        }
+    }
+}
+
+/// Checks that the prompt can be answered, and starts its timeout, when running without a terminal
+fn start_lines<P, T>(prompt: &mut P) -> Result<(), PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    prompt.check()?;
+    let core = prompt.core();
+    core.deadline = core.timeout.map(|timeout| Instant::now() + timeout);
+    Ok(())
+}
+
+/// Submits the default answer of a prompt running without a terminal whose timeout expired
+///
+/// Fails with `PromptError::Timeout` if the prompt has none.
+fn time_out_lines<P, T>(prompt: &mut P) -> Result<T, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    writeln!(prompt.core().output)?;
+    if prompt.submit_default() {
+        prompt.core().state = PromptState::Success;
+        Ok(prompt.result())
+    } else {
+        prompt.core().state = PromptState::Aborted;
+        Err(PromptError::Timeout)
+    }
+}
+
+/// Prints the question of a prompt running without a terminal
+fn ask<P, T>(prompt: &mut P) -> Result<(), PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    let question = prompt.line_question().ok_or(PromptError::NotATty)?;
+    let output = &mut prompt.core().output;
+    write!(output, "? {} {} ", question, Figures::PointerSmall.as_str())?;
+    output.flush()?;
+    Ok(())
+}
+
+/// Completes when the timeout of the prompt expires, never if it has none
+#[cfg(feature = "async")]
+fn expiry(core: &PromptCore) -> impl Future<Output = ()> + Unpin {
+    match core.deadline {
+        Some(deadline) => {
+            Delay::new(deadline.saturating_duration_since(Instant::now())).left_future()
+        }
+        None => future::pending().right_future(),
+    }
+}
+
+/// Answers the prompt with a line of input, waiting for its background validation if any
+///
+/// Prints why the line isn't an answer otherwise. `None` means the input ended.
+fn answer_line_blocking<P, T>(
+    prompt: &mut P,
+    line: Option<String>,
+) -> Result<Option<T>, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    let answer = match parse_line(prompt, line)? {
+        Some(answer) => answer,
+        None => return Ok(None),
+    };
+    if let Err(msg) = validate_line_blocking(prompt) {
+        print_line_error(prompt, &msg)?;
+        return Ok(None);
+    }
+    prompt.core().state = PromptState::Success;
+    Ok(Some(answer))
+}
+
+/// Parses a line of input into the answer, or prints why it isn't one
+///
+/// `None` means the input ended.
+fn parse_line<P, T>(prompt: &mut P, line: Option<String>) -> Result<Option<T>, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    writeln!(prompt.core().output)?;
+    let line = line.ok_or(PromptError::Eof)?;
+    match prompt.parse_line(line.trim_end_matches(['\n', '\r'])) {
+        Ok(answer) => Ok(Some(answer)),
+        Err(msg) => {
+            print_line_error(prompt, &msg)?;
+            Ok(None)
+        }
+    }
+}
+
+/// Prints why a line of input isn't an answer, before asking again
+fn print_line_error<P, T>(prompt: &mut P, msg: &str) -> Result<(), PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    let output = &mut prompt.core().output;
+    writeln!(output, "{} {}", Figures::PointerSmall.as_str(), msg)?;
+    Ok(())
+}
+
+/// Runs the background validation of a parsed line, if any, blocking the current thread
+#[cfg(feature = "async")]
+fn validate_line_blocking<P, T>(prompt: &mut P) -> Result<(), String>
+where
+    P: Prompt<T> + ?Sized,
+{
+    match prompt.validate_async() {
+        Some(validation) => block_on(validation),
+        None => Ok(()),
+    }
+}
+#[cfg(not(feature = "async"))]
+fn validate_line_blocking<P, T>(_prompt: &mut P) -> Result<(), String>
+where
+    P: Prompt<T> + ?Sized,
+{
+    Ok(())
+}
+
+/// Lines of input, read on a thread of their own
+///
+/// So waiting for a line can be given up, e.g. when the prompt is cancelled.
+/// A line that arrives after that is kept for the next prompt.
+#[derive(Clone)]
+struct Lines(Arc<LinesShared>);
+/// Appends a line and returns its length, like `BufRead::read_line`
+type ReadLine = Box<dyn FnMut(&mut String) -> io::Result<usize> + Send>;
+struct LinesShared {
+    read: Mutex<ReadLine>,
+    state: Mutex<LinesState>,
+    arrived: Condvar,
+}
+#[derive(Default)]
+struct LinesState {
+    /// The line read but not taken yet, `None` inside if the input ended
+    line: Option<io::Result<Option<String>>>,
+    /// Whether the thread is reading a line
+    reading: bool,
+    /// Futures waiting for the line
+    #[cfg(feature = "async")]
+    wakers: Vec<Waker>,
+}
+impl Lines {
+    /// Returns the lines read by `read`
+    fn new<F>(read: F) -> Lines
+    where
+        F: FnMut(&mut String) -> io::Result<usize> + Send + 'static,
+    {
+        Lines(Arc::new(LinesShared {
+            read: Mutex::new(Box::new(read)),
+            state: Mutex::new(LinesState::default()),
+            arrived: Condvar::new(),
+        }))
+    }
+
+    /// The lines of stdin, shared by all prompts
+    fn stdin() -> Lines {
+        static STDIN: OnceLock<Lines> = OnceLock::new();
+        STDIN
+            .get_or_init(|| Lines::new(|line| stdin().read_line(line)))
+            .clone()
+    }
+
+    /// Takes the line read, or starts reading one if there is none yet
+    fn take(&self, state: &mut LinesState) -> Option<io::Result<Option<String>>> {
+        let line = state.line.take();
+        if line.is_none() && !state.reading {
+            state.reading = true;
+            let lines = self.clone();
+            thread::spawn(move || lines.read());
+        }
+        line
+    }
+
+    /// Reads a line, on the thread started by `take`
+    fn read(&self) {
+        let mut line = String::new();
+        let read = (self.0.read.lock().unwrap())(&mut line);
+        let mut state = self.0.state.lock().unwrap();
+        state.line = Some(read.map(|read| Some(line).filter(|_| read > 0)));
+        state.reading = false;
+        #[cfg(feature = "async")]
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+        self.0.arrived.notify_all();
+    }
+
+    /// Waits for the next line, `None` if the input ended
+    #[cfg(feature = "async")]
+    async fn next(&self) -> io::Result<Option<String>> {
+        future::poll_fn(|cx| {
+            let mut state = self.0.state.lock().unwrap();
+            match self.take(&mut state) {
+                Some(line) => Poll::Ready(line),
+                None => {
+                    if !state.wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                        state.wakers.push(cx.waker().clone());
+                    }
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Waits for the next line until the deadline, if any, blocking the current thread
+    ///
+    /// Returns `None` if the deadline passed first, and `Some(Ok(None))` if the input ended.
+    fn next_blocking(&self, deadline: Option<Instant>) -> Option<io::Result<Option<String>>> {
+        let mut state = self.0.state.lock().unwrap();
+        loop {
+            if let Some(line) = self.take(&mut state) {
+                return Some(line);
+            }
+            state = match deadline {
+                Some(deadline) => {
+                    let left = deadline.saturating_duration_since(Instant::now());
+                    if left == Duration::from_secs(0) {
+                        return None;
+                    }
+                    self.0.arrived.wait_timeout(state, left).unwrap().0
+                }
+                None => self.0.arrived.wait(state).unwrap(),
+            };
+        }
+    }
+}
+
+/// A running prompt, and the terminal it runs in if the driver set that up
+///
+/// If dropped before the prompt is done, i.e. when the future running it is
+/// dropped, the prompt is shown as aborted, like after pressing ESC.
+/// The terminal is restored after that.
+struct Session<'a, P, T>
+where
+    P: Prompt<T> + ?Sized,
+{
+    prompt: &'a mut P,
+    terminal: Option<TerminalGuard>,
+    running: bool,
+    answer: PhantomData<fn() -> T>,
+}
+impl<'a, P, T> Session<'a, P, T>
+where
+    P: Prompt<T> + ?Sized,
+{
+    fn new(prompt: &'a mut P, terminal: Option<TerminalGuard>) -> Session<'a, P, T> {
+        Session {
+            prompt,
+            terminal,
+            running: true,
+            answer: PhantomData,
+        }
+    }
+
+    /// Ends the session with what running the prompt returned
+    fn finish(mut self, result: Result<T, PromptError>) -> Result<T, PromptError> {
+        self.running = false;
+        result
+    }
+}
+impl<'a, P, T> Drop for Session<'a, P, T>
+where
+    P: Prompt<T> + ?Sized,
+{
+    fn drop(&mut self) {
+        let mut done = self.prompt.core().state.is_done();
+        // Rendering while panicking risks panicking again, leave that to the terminal guard
+        if self.running && !done && !thread::panicking() {
+            self.prompt.core().state = PromptState::Aborted;
+            done = self.prompt.display().is_ok();
+        }
+        // A prompt that is done already left the cursor on a fresh line
+        if let Some(terminal) = &mut self.terminal {
+            if done {
+                terminal.finish();
+            }
+        }
+    }
+}
+
+/// Puts the terminal in raw mode, and captures the mouse if the prompt wants it
+fn start_terminal<P, T>(prompt: &mut P) -> Result<TerminalGuard, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    let mut guard = TerminalGuard::new(prompt.core().output.clone())?;
+    if prompt.core().mouse {
+        guard.capture_mouse()?;
+        // Without it clicks may land on the wrong row, which is no reason to fail
+        if let Ok((_, row)) = cursor::position() {
+            prompt.core().top_row = row;
+        }
+    }
+    Ok(guard)
+}
+
+/// Starts the timeout of the prompt and renders it for the first time
+///
+/// The prompt was checked already, before the terminal was set up.
+fn start<P, T>(prompt: &mut P) -> Result<(), PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    let core = prompt.core();
+    core.deadline = core.timeout.map(|timeout| Instant::now() + timeout);
+    prompt.display()?;
+    Ok(())
+}
+
+/// Returns how long to wait for an event before the timer needs handling
+///
+/// That is until the timeout expires or, with a countdown, until the
+/// number of seconds left changes. `None` if the prompt has no timeout.
+fn next_tick(core: &PromptCore) -> Option<Duration> {
+    let left = core.deadline?.saturating_duration_since(Instant::now());
+    if !core.countdown {
+        return Some(left);
+    }
+    let second = Duration::from_secs(1);
+    let fraction = Duration::from_nanos((left.as_nanos() % second.as_nanos()) as u64);
+    if fraction > Duration::from_secs(0) {
+        Some(fraction)
+    } else {
+        Some(cmp::min(left, second))
+    }
+}
+
+/// Whether the timeout of the prompt has expired
+fn expired(core: &PromptCore) -> bool {
+    matches!(core.deadline, Some(deadline) if Instant::now() >= deadline)
+}
+
+/// Handles the timer firing, returning the default answer if the timeout expired
+fn handle_tick<P, T>(prompt: &mut P) -> Result<Option<T>, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    if expired(prompt.core()) {
+        return time_out(prompt).map(Some);
+    }
+    // Only the countdown changed
+    prompt.display()?;
+    Ok(None)
+}
+
+/// Submits the default answer of a prompt whose timeout expired, if it has one
+fn time_out<P, T>(prompt: &mut P) -> Result<T, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    if prompt.submit_default() {
+        prompt.core().state = PromptState::Success;
+        prompt.display()?;
+        Ok(prompt.result())
+    } else {
+        prompt.core().state = PromptState::Aborted;
+        prompt.display()?;
+        Err(PromptError::Timeout)
+    }
+}
+
+/// Handles a single event, returning the answer once the prompt succeeded
+///
+/// `None` means the event source ran out.
+fn handle_event<P, T>(
+    prompt: &mut P,
+    event: Option<crossterm::Result<Event>>,
+) -> Result<Option<T>, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    match event {
+        Some(Ok(Event::Key(event))) => match abort_error(event) {
+            Some(error) => {
+                prompt.core().state = PromptState::Aborted;
+                prompt.display()?;
+                return Err(error);
+            }
+            None if prompt.core().state == PromptState::Validating => {}
+            None => prompt.handle_key_event(event),
+        },
+        // What is on screen may have been wrapped or cut off by the terminal,
+        // so start over from a clean screen
+        Some(Ok(Event::Resize(columns, rows))) => {
+            let core = prompt.core();
+            core.output.resize(columns, rows);
+            core.top_row = 0;
+            core.renderer.reset();
+            queue!(core.output, Clear(ClearType::All), cursor::MoveTo(0, 0))?;
+        }
+        Some(Ok(Event::Mouse(event))) => {
+            if prompt.core().mouse && prompt.core().state != PromptState::Validating {
+                prompt.handle_mouse_event(event);
+            }
+        }
+        Some(Err(e)) => return Err(e.into()),
+        None => return Err(PromptError::Eof),
+    }
+
+    if prompt.core().state == PromptState::Validate {
+        let result = prompt.validate();
+        if result.is_err() || !start_validation(prompt) {
+            apply_validation(prompt.core(), result);
+        }
+    }
+
+    prompt.display()?;
+    Ok(answer(prompt))
+}
+
+/// The answer, if the prompt succeeded
+fn answer<P, T>(prompt: &mut P) -> Option<T>
+where
+    P: Prompt<T> + ?Sized,
+{
+    if prompt.core().state == PromptState::Success {
+        Some(prompt.result())
+    } else {
+        None
+    }
+}
+
+/// Finishes the prompt if the answer is valid, otherwise stores the error to show
+fn apply_validation(core: &mut PromptCore, result: Result<(), String>) {
+    match result {
+        Ok(()) => {
+            core.error = None;
+            core.state = PromptState::Success;
+        }
+        Err(msg) => {
+            core.error = Some(msg);
+            core.state = PromptState::Running;
+        }
+    }
+}
+
+/// Starts validating the answer in the background, returning false if the prompt doesn't
+#[cfg(feature = "async")]
+fn start_validation<P, T>(prompt: &mut P) -> bool
+where
+    P: Prompt<T> + ?Sized,
+{
+    match prompt.validate_async() {
+        Some(validation) => {
+            let core = prompt.core();
+            core.validation = Some(validation);
+            core.state = PromptState::Validating;
+            true
+        }
+        None => false,
+    }
+}
+#[cfg(not(feature = "async"))]
+fn start_validation<P, T>(_prompt: &mut P) -> bool
+where
+    P: Prompt<T> + ?Sized,
+{
+    false
+}
+
+/// Waits for the background validation of the answer, if any, blocking the current thread
+#[cfg(feature = "async")]
+fn wait_for_validation<P, T>(prompt: &mut P) -> Result<Option<T>, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    let core = prompt.core();
+    match core.validation.take() {
+        Some(validation) => {
+            apply_validation(core, block_on(validation));
+            prompt.display()?;
+            Ok(answer(prompt))
+        }
+        None => Ok(None),
+    }
+}
+#[cfg(not(feature = "async"))]
+fn wait_for_validation<P, T>(_prompt: &mut P) -> Result<Option<T>, PromptError>
+where
+    P: Prompt<T> + ?Sized,
+{
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::confirm::ConfirmPrompt;
+    use crate::utils::{key_events, typed, Output};
+    use crossterm::event::{KeyCode, KeyEvent};
+    use std::io::{sink, BufReader, Read};
+    use std::sync::mpsc::{channel, Sender};
+
+    /// Smallest possible prompt: answers with the first key typed, unless it is a digit
+    struct KeyPrompt {
+        core: PromptCore,
+        key: char,
+        displayed: usize,
+    }
+    impl KeyPrompt {
+        fn new() -> KeyPrompt {
+            KeyPrompt {
+                core: PromptCore {
+                    output: Output::new(sink()),
+                    ..Default::default()
+                },
+                key: ' ',
+                displayed: 0,
+            }
+        }
+    }
+    impl Prompt<char> for KeyPrompt {
+        fn core(&mut self) -> &mut PromptCore {
+            &mut self.core
+        }
+        fn display(&mut self) -> crossterm::Result<()> {
+            self.displayed += 1;
+            Ok(())
+        }
+        fn handle_key_event(&mut self, event: KeyEvent) {
+            if let KeyCode::Char(c) = event.code {
+                self.key = c;
+                self.core.state = PromptState::Validate;
+            }
+        }
+        fn validate(&mut self) -> Result<(), String> {
+            match self.key.is_ascii_digit() {
+                true => Err("No digits".to_string()),
+                false => Ok(()),
+            }
+        }
+        fn result(&self) -> char {
+            self.key
+        }
+        fn line_question(&self) -> Option<String> {
+            Some("Key?".to_string())
+        }
+        fn parse_line(&mut self, line: &str) -> Result<char, String> {
+            self.key = line.chars().next().ok_or("Type a key")?;
+            self.validate()?;
+            Ok(self.key)
+        }
+    }
+
+    #[test]
+    fn custom_prompt_keeps_going_until_valid() {
+        let mut prompt = KeyPrompt::new();
+        let result = prompt.run_blocking_with_events(key_events(typed("1a")));
+        assert_eq!(result.unwrap(), 'a');
+        assert_eq!(prompt.core.state, PromptState::Success);
+        assert_eq!(prompt.core.error, None);
+        assert_eq!(prompt.displayed, 3);
+    }
+
+    #[test]
+    fn custom_prompt_shows_validation_error() {
+        let mut prompt = KeyPrompt::new();
+        let result = prompt.run_blocking_with_events(key_events(typed("1")));
+        assert!(result.is_err());
+        assert_eq!(prompt.core.state, PromptState::Running);
+        assert_eq!(prompt.core.error, Some("No digits".to_string()));
+    }
+
+    #[test]
+    fn custom_prompt_as_trait_object() {
+        let mut prompt: Box<dyn Prompt<char>> = Box::new(KeyPrompt::new());
+        let result = run_blocking_with_events(prompt.as_mut(), key_events(typed("1a")));
+        assert_eq!(result.unwrap(), 'a');
+    }
+
+    #[test]
+    fn invalid_prompt_shows_nothing() {
+        use crate::{select::SelectPrompt, testing::VirtualTerminal};
+
+        let terminal = VirtualTerminal::new(20, 5);
+        let mut prompt =
+            SelectPrompt::new("Pick", Vec::<&str>::new()).with_output(terminal.output());
+        match prompt.run_blocking_with_events(key_events(typed("a"))) {
+            Err(PromptError::InvalidConfiguration(_)) => {}
+            _ => panic!("expected an InvalidConfiguration error"),
+        }
+        assert!(terminal.frames().is_empty());
+    }
+
+    #[test]
+    fn custom_prompt_aborted() {
+        let mut prompt = KeyPrompt::new();
+        let events = vec![KeyEvent::from(KeyCode::Esc)];
+        match prompt.run_blocking_with_events(key_events(events)) {
+            Err(PromptError::Cancelled) => {}
+            _ => panic!("expected a Cancelled error"),
+        }
+        assert_eq!(prompt.core.state, PromptState::Aborted);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn custom_prompt_cancelled_by_dropping() {
+        use futures::{executor::block_on, poll, stream};
+
+        let mut prompt = KeyPrompt::new();
+        block_on(async {
+            let mut run = prompt.run_with_events(stream::pending());
+            assert!(poll!(&mut run).is_pending());
+        });
+        assert_eq!(prompt.core.state, PromptState::Aborted);
+        assert_eq!(prompt.displayed, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn custom_prompt_not_aborted_after_error() {
+        use futures::executor::block_on;
+
+        let mut prompt = KeyPrompt::new();
+        let result = block_on(prompt.run_with_events(key_events(typed("1"))));
+        assert!(result.is_err());
+        assert_eq!(prompt.core.state, PromptState::Running);
+        assert_eq!(prompt.displayed, 2);
+    }
+
+    /// Input that takes some time before it can be read
+    struct Wait<'a>(Duration, &'a [u8]);
+    impl Read for Wait<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            thread::sleep(self.0);
+            self.1.read(buf)
+        }
+    }
+
+    /// Lines sent through the returned sender, read when asked for
+    fn sent_lines() -> (Sender<&'static str>, Lines) {
+        let (sender, receiver) = channel::<&'static str>();
+        let lines = Lines::new(move |line| {
+            let text = receiver.recv().unwrap_or("");
+            line.push_str(text);
+            Ok(text.len())
+        });
+        (sender, lines)
+    }
+
+    #[test]
+    fn custom_prompt_answered_line_by_line() {
+        let (sender, lines) = sent_lines();
+        sender.send("1\n").unwrap();
+        sender.send("a\n").unwrap();
+        let mut prompt = KeyPrompt::new();
+        assert_eq!(run_lines_blocking(&mut prompt, &lines).unwrap(), 'a');
+        assert_eq!(prompt.core.state, PromptState::Success);
+
+        drop(sender);
+        let mut prompt = KeyPrompt::new();
+        match run_lines_blocking(&mut prompt, &lines) {
+            Err(PromptError::Eof) => {}
+            _ => panic!("expected an Eof error"),
+        }
+    }
+
+    #[test]
+    fn custom_prompt_lines_time_out() {
+        let (_sender, lines) = sent_lines();
+        let mut prompt = KeyPrompt::new();
+        prompt.core.timeout = Some(Duration::from_millis(10));
+        match run_lines_blocking(&mut prompt, &lines) {
+            Err(PromptError::Timeout) => {}
+            _ => panic!("expected a Timeout error"),
+        }
+        assert_eq!(prompt.core.state, PromptState::Aborted);
+
+        let mut prompt = ConfirmPrompt::new("Sure?")
+            .set_initial(true)
+            .with_timeout(Duration::from_millis(10))
+            .with_output(Output::new(sink()));
+        assert!(run_lines_blocking(&mut prompt, &lines).unwrap());
+
+        let input = Wait(Duration::from_millis(20), "n\n".as_bytes());
+        let mut prompt = ConfirmPrompt::new("Sure?")
+            .set_initial(true)
+            .with_timeout(Duration::from_millis(10))
+            .with_output(Output::new(sink()));
+        assert!(prompt.run_lines(BufReader::new(input)).unwrap());
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn custom_prompt_awaited_lines_time_out() {
+        use futures::executor::block_on;
+
+        let (_sender, lines) = sent_lines();
+        let mut prompt = KeyPrompt::new();
+        prompt.core.timeout = Some(Duration::from_millis(10));
+        match block_on(run_lines_async(&mut prompt, &lines)) {
+            Err(PromptError::Timeout) => {}
+            _ => panic!("expected a Timeout error"),
+        }
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "async")]
+    async fn lines_validated_on_the_runtime() {
+        use crate::{text::TextPrompt, utils::Validation};
+
+        let (sender, lines) = sent_lines();
+        sender.send("taken\n").unwrap();
+        sender.send("free\n").unwrap();
+        let mut prompt = TextPrompt::new("Name?")
+            .with_async_validator(|input| {
+                Validation::new(async move {
+                    // Only makes progress if the runtime isn't blocked
+                    tokio::time::delay_for(Duration::from_millis(1)).await;
+                    match input.as_str() {
+                        "taken" => Err("Name is taken".to_string()),
+                        _ => Ok(()),
+                    }
+                })
+            })
+            .with_output(Output::new(sink()));
+        assert_eq!(run_lines_async(&mut prompt, &lines).await.unwrap(), "free");
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn custom_prompt_awaits_lines() {
+        use futures::{executor::block_on, poll};
+
+        let (sender, lines) = sent_lines();
+        let mut prompt = KeyPrompt::new();
+        block_on(async {
+            let run = run_lines_async(&mut prompt, &lines);
+            pin_mut!(run);
+            assert!(poll!(run).is_pending());
+        });
+
+        // The line arriving after the prompt gave up answers the next one
+        sender.send("b\n").unwrap();
+        let mut prompt = KeyPrompt::new();
+        assert_eq!(block_on(run_lines_async(&mut prompt, &lines)).unwrap(), 'b');
+    }
+}
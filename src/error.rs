@@ -0,0 +1,58 @@
+//! Errors returned by prompts
+
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+/// Why a prompt did not return an answer
+#[derive(Debug)]
+pub enum PromptError {
+    /// Reading from or writing to the terminal failed
+    Io(io::Error),
+    /// The prompt needs a terminal, but there is none
+    NotATty,
+    /// The user pressed CTRL+c
+    Interrupted,
+    /// The user pressed ESC, or the program cancelled the prompt, see `Prompt::run_until`
+    Cancelled,
+    /// The user pressed CTRL+d, or the input ended before the prompt was done
+    Eof,
+    /// No answer was given in time
+    Timeout,
+    /// The prompt was set up in a way that can't be answered, e.g. without any choices
+    InvalidConfiguration(String),
+}
+impl fmt::Display for PromptError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PromptError::Io(e) => write!(fmt, "terminal error: {}", e),
+            PromptError::NotATty => write!(fmt, "not a terminal"),
+            PromptError::Interrupted => write!(fmt, "prompt was interrupted"),
+            PromptError::Cancelled => write!(fmt, "prompt was cancelled"),
+            PromptError::Eof => write!(fmt, "input ended before the prompt was answered"),
+            PromptError::Timeout => write!(fmt, "prompt timed out"),
+            PromptError::InvalidConfiguration(msg) => write!(fmt, "invalid prompt: {}", msg),
+        }
+    }
+}
+impl Error for PromptError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PromptError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+impl From<io::Error> for PromptError {
+    fn from(e: io::Error) -> PromptError {
+        PromptError::Io(e)
+    }
+}
+impl From<crossterm::ErrorKind> for PromptError {
+    fn from(e: crossterm::ErrorKind) -> PromptError {
+        match e {
+            crossterm::ErrorKind::IoError(e) => PromptError::Io(e),
+            e => PromptError::Io(io::Error::other(e.to_string())),
+        }
+    }
+}
@@ -1,18 +1,175 @@
+#![allow(deprecated)] // crossterm 0.14's queue! macro calls the deprecated Error::description
+
 pub mod autocomplete;
 pub mod confirm;
+mod driver;
+pub mod error;
+pub mod multiselect;
+pub mod number;
 pub mod select;
+pub mod testing;
 pub mod text;
+pub mod toggle;
 pub mod utils;
 
+#[cfg(feature = "async")]
 use async_trait::async_trait;
-use crossterm::event::KeyEvent;
+use crossterm::event::{Event, KeyEvent, MouseEvent};
+use error::PromptError;
+#[cfg(feature = "async")]
+use futures::{Future, Stream};
+use std::io::BufRead;
+use utils::PromptCore;
 
 /// Base prompt trait
 ///
 /// You must `use` this when using any of the prompts in this crate
-#[async_trait]
+///
+/// All prompts share the same driver, which reads events, hands key presses
+/// to `handle_key_event`, calls `validate` when the prompt enters
+/// `PromptState::Validate`, renders using `display` after every event and
+/// returns `result` once the prompt is in `PromptState::Success`.
+/// So a custom prompt only has to implement `core`, `display`,
+/// `handle_key_event` and `result`. To also be answerable without a terminal,
+/// it implements `line_question` and `parse_line`.
+///
+/// Every runner calls `check` before showing or asking anything.
+#[cfg_attr(feature = "async", async_trait)]
 pub trait Prompt<T> {
-    async fn run(&mut self) -> std::result::Result<Option<T>, crossterm::ErrorKind>;
+    /// Runs the prompt in the terminal
+    ///
+    /// Stops either when the user submits an answer, an error occurs,
+    /// or the prompt is aborted by the user using CTRL+c, CTRL+d or ESC,
+    /// which return `PromptError::Interrupted`, `Eof` and `Cancelled` respectively.
+    /// If the prompt has a timeout and it expires, the default answer is
+    /// submitted, or `PromptError::Timeout` returned if there is none.
+    ///
+    /// If stdin is not a terminal (e.g. `echo y | mytool`) the answer is read
+    /// line by line instead, see `run_lines`.
+    #[cfg(feature = "async")]
+    async fn run(&mut self) -> std::result::Result<T, PromptError>
+    where
+        Self: Send,
+        T: Send,
+    {
+        driver::run(self).await
+    }
+    /// Runs the prompt in the terminal until it is done or `cancel` completes
+    ///
+    /// Lets the program abort the prompt, e.g. when a background job finishes
+    /// or a signal arrives. Cancelling shows the prompt as aborted, restores
+    /// the terminal and returns `PromptError::Cancelled`, like pressing ESC.
+    /// Simply dropping the future returned by `run` cleans up the same way.
+    #[cfg(feature = "async")]
+    async fn run_until<F>(&mut self, cancel: F) -> std::result::Result<T, PromptError>
+    where
+        Self: Send + Sized,
+        T: Send,
+        F: Future<Output = ()> + Send,
+    {
+        driver::run_until(self, cancel).await
+    }
+    /// Runs the prompt, reading events from the given stream instead of the terminal
+    ///
+    /// Raw mode is left untouched, so this also works without a TTY.
+    /// Combined with `utils::key_events` and `utils::Output` this allows
+    /// running a prompt headlessly, e.g. in tests.
+    /// Returns `PromptError::Eof` if the stream ends before the prompt is done.
+    #[cfg(feature = "async")]
+    async fn run_with_events<S>(&mut self, events: S) -> std::result::Result<T, PromptError>
+    where
+        Self: Send + Sized,
+        S: Stream<Item = crossterm::Result<Event>> + Unpin + Send,
+    {
+        driver::run_with_events(self, events).await
+    }
+    /// Runs the prompt in the terminal, blocking the current thread
+    ///
+    /// Like `run`, but needs no async runtime.
+    fn run_blocking(&mut self) -> std::result::Result<T, PromptError> {
+        driver::run_blocking(self)
+    }
+    /// Runs the prompt, blocking the current thread, reading events from
+    /// the given iterator instead of the terminal
+    ///
+    /// See `run_with_events`.
+    fn run_blocking_with_events<I>(&mut self, events: I) -> std::result::Result<T, PromptError>
+    where
+        Self: Sized,
+        I: Iterator<Item = crossterm::Result<Event>>,
+    {
+        driver::run_blocking_with_events(self, events)
+    }
+    /// Runs the prompt without a terminal, reading the answer line by line
+    ///
+    /// Prints the question from `line_question`, reads a line and answers it
+    /// with `parse_line`, asking again if the answer is invalid.
+    /// Returns `PromptError::Eof` if the input ends first.
+    /// Prompts that can't be answered this way return `PromptError::NotATty`.
+    ///
+    /// Reading from `input` can't be interrupted, so if the prompt has a timeout,
+    /// it is only checked once a line arrives. `run` and `run_blocking` reading
+    /// from stdin stop waiting as soon as it expires.
+    fn run_lines<R>(&mut self, input: R) -> std::result::Result<T, PromptError>
+    where
+        Self: Sized,
+        R: BufRead,
+    {
+        driver::run_lines(self, input)
+    }
+    /// The question to ask when running without a terminal, including any hint of what to answer
+    ///
+    /// Returns None by default, meaning the prompt can't be answered line by line.
+    fn line_question(&self) -> Option<String> {
+        None
+    }
+    /// Answers the prompt with a line of input (without line ending), when running without a terminal
+    ///
+    /// Returns the answer, or an error message to show before asking again.
+    /// Only called if the prompt has a `line_question`.
+    fn parse_line(&mut self, _line: &str) -> std::result::Result<T, String> {
+        Err("This prompt can't be answered line by line".to_string())
+    }
+    /// Checks that the prompt can be answered at all, before it is displayed
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        Ok(())
+    }
+    /// Makes the default answer the result, for when the timeout expires
+    ///
+    /// Returns false if the prompt has no default answer, in which case
+    /// running it fails with `PromptError::Timeout`. Has no default by default.
+    fn submit_default(&mut self) -> bool {
+        false
+    }
+    /// Checks the answer when the prompt enters `PromptState::Validate`
+    ///
+    /// On success the prompt is done, otherwise the error message is stored
+    /// in `PromptCore::error` for `display` to show and the prompt keeps running.
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        Ok(())
+    }
+    /// Starts checking the answer in the background, once `validate` accepted it
+    ///
+    /// If this returns a validation, the prompt is in `PromptState::Validating`
+    /// until it completes, ignoring key presses other than those that abort it.
+    /// Its outcome is then handled like the result of `validate`.
+    /// The blocking runners wait for it, blocking the current thread.
+    /// Without a terminal it checks the answer `parse_line` returned.
+    /// Returns None by default.
+    #[cfg(feature = "async")]
+    fn validate_async(&mut self) -> Option<utils::Validation> {
+        None
+    }
+    /// State, output and error shared with the driver
+    fn core(&mut self) -> &mut PromptCore;
+    /// Renders the prompt in its current state
     fn display(&mut self) -> crossterm::Result<()>;
+    /// Updates the prompt after a key press
     fn handle_key_event(&mut self, event: KeyEvent);
+    /// Updates the prompt after a mouse event
+    ///
+    /// Only called if `PromptCore::mouse` is set, does nothing by default.
+    fn handle_mouse_event(&mut self, _event: MouseEvent) {}
+    /// The answer, once the prompt is in `PromptState::Success`
+    fn result(&self) -> T;
 }
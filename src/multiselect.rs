@@ -0,0 +1,1021 @@
+//! Interactive prompts where the user chooses any number of options from a list
+
+use crate::{
+    error::PromptError,
+    utils::{
+        find_choice, is_abort_event, print_input_icon, print_state_icon, Figures, Frame, ListEntry,
+        ListView, Output, PromptCore, PromptState, TextInput,
+    },
+    Prompt,
+};
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent},
+    queue,
+    style::{style, Attribute, Color, StyledContent},
+};
+use std::cmp;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::io::Write;
+use std::time::Duration;
+
+/// How many options must and can be selected
+#[derive(Debug, Default)]
+struct Limits {
+    min: usize,
+    max: Option<usize>,
+}
+impl Limits {
+    /// Checks that the number of selected options is within min and max
+    fn check_count(&self, count: usize) -> std::result::Result<(), String> {
+        let options = |n| match n {
+            1 => "1 option".to_string(),
+            n => format!("{} options", n),
+        };
+        if count < self.min {
+            return Err(format!("Select at least {}", options(self.min)));
+        }
+        match self.max {
+            Some(max) if count > max => Err(format!("Select at most {}", options(max))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that enough of the choices can be selected, and that the selected ones exist
+    fn check<T>(
+        &self,
+        choices: &[T],
+        selected: &BTreeSet<usize>,
+    ) -> std::result::Result<(), PromptError> {
+        if choices.is_empty() {
+            return Err(PromptError::InvalidConfiguration(
+                "there are no choices to choose from".to_string(),
+            ));
+        }
+        let possible = cmp::min(choices.len(), self.max.unwrap_or(usize::MAX));
+        if self.min > possible {
+            return Err(PromptError::InvalidConfiguration(format!(
+                "at least {} choices must be selected, but only {} can be",
+                self.min, possible
+            )));
+        }
+        if let Some(index) = selected.iter().find(|&&i| i >= choices.len()) {
+            return Err(PromptError::InvalidConfiguration(format!(
+                "selected choice {} does not exist",
+                index
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Parses a comma-separated list of the texts of options, or their (zero-based) indices
+///
+/// An empty line keeps the options that are already selected.
+fn parse_selection<T: std::fmt::Display>(
+    line: &str,
+    choices: &[T],
+    selected: &BTreeSet<usize>,
+    limits: &Limits,
+) -> std::result::Result<BTreeSet<usize>, String> {
+    let selected = match line.trim() {
+        "" => selected.clone(),
+        line => line
+            .split(',')
+            .map(|choice| find_choice(choices, choice.trim()))
+            .collect::<std::result::Result<_, _>>()?,
+    };
+    limits.check_count(selected.len())?;
+    Ok(selected)
+}
+
+/// Returns the checkbox shown in front of an option
+fn radio(selected: bool) -> StyledContent<&'static str> {
+    if selected {
+        style(Figures::RadioOn.as_str()).with(Color::Green)
+    } else {
+        style(Figures::RadioOff.as_str())
+    }
+}
+
+/// Prints the selected options, separated by commas
+fn print_selected<T: std::fmt::Display>(
+    frame: &mut Frame,
+    choices: &[T],
+    selected: &BTreeSet<usize>,
+) {
+    let answer = selected
+        .iter()
+        .map(|&i| choices[i].to_string())
+        .collect::<Vec<_>>();
+    frame.print(answer.join(", "));
+}
+
+/// Prints the error below the options, if there is one
+fn print_error(frame: &mut Frame, error: &Option<String>) {
+    if let Some(msg) = error {
+        frame.new_line();
+        frame.print(format!("{} ", Figures::PointerSmall.as_str()));
+        frame.print_styled(style(msg).with(Color::Red).attribute(Attribute::Italic));
+    }
+}
+
+/// Returns how many rows the error below the options takes up
+fn error_rows(error: &Option<String>, columns: u16) -> u16 {
+    let mut footer = Frame::new();
+    print_error(&mut footer, error);
+    // Not counting the empty line the error starts after
+    footer.rows(columns) - 1
+}
+
+/// Selects the option if it isn't, deselects it otherwise
+fn toggle(selected: &mut BTreeSet<usize>, index: usize) {
+    if !selected.remove(&index) {
+        selected.insert(index);
+    }
+}
+
+/// Interactive prompt where the user chooses any number of options from a list
+///
+/// Shows a list of options. Use <kbd>up</kbd>/<kbd>down</kbd> to navigate,
+/// <kbd>space</kbd> to select or deselect an option and <kbd>enter</kbd> to submit.
+/// <kbd>a</kbd> selects all options, <kbd>n</kbd> none and <kbd>i</kbd> inverts the selection.
+/// Use `with_min` and `with_max` to limit how many options can be submitted.
+/// The data vector can have a custom type but it must implement
+/// `std::fmt::Display` as well as `std::clone::Clone` and `std::marker::Send`.
+///
+/// # Examples
+///
+/// ```
+/// use prompts::{error::PromptError, Prompt, multiselect::MultiSelectPrompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
+///
+/// let data = vec!["serde", "tokio", "rayon", "clap"];
+/// let mut prompt = MultiSelectPrompt::new("Which features?", data).set_selected(vec![0]);
+///
+/// match prompt.run().await {
+///     Ok(features) => println!("You chose: {:?}", features),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
+/// }
+/// # }
+/// ```
+pub struct MultiSelectPrompt<T> {
+    message: String,
+    core: PromptCore,
+    choices: Vec<T>,
+    selected: BTreeSet<usize>,
+    current: usize,
+    list: ListView,
+    limits: Limits,
+}
+impl<T: std::fmt::Debug> fmt::Debug for MultiSelectPrompt<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("MultiSelectPrompt")
+            .field("message", &self.message)
+            .field("choices", &self.choices)
+            .field("selected", &self.selected)
+            .field("min", &self.limits.min)
+            .field("max", &self.limits.max)
+            .finish()
+    }
+}
+impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> MultiSelectPrompt<T> {
+    /// Returns a MultiSelectPrompt ready to be run
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to display to the user before the prompt
+    /// * `choices` - A vector of options that the user can choose from
+    pub fn new<S>(message: S, choices: Vec<T>) -> MultiSelectPrompt<T>
+    where
+        S: Into<String>,
+    {
+        MultiSelectPrompt {
+            message: message.into(),
+            choices,
+            core: PromptCore::default(),
+            selected: BTreeSet::new(),
+            current: 0,
+            list: ListView::new(10),
+            limits: Limits::default(),
+        }
+    }
+
+    /// Set which options are selected initially
+    ///
+    /// # Arguments
+    ///
+    /// * `selected` - The (zero-based) indices of the selected options
+    pub fn set_selected(mut self, selected: Vec<usize>) -> MultiSelectPrompt<T> {
+        self.selected = selected.into_iter().collect();
+        self
+    }
+
+    /// Set how many options must be selected at least
+    pub fn with_min(mut self, min: usize) -> MultiSelectPrompt<T> {
+        self.limits.min = min;
+        self
+    }
+
+    /// Set how many options can be selected at most
+    pub fn with_max(mut self, max: usize) -> MultiSelectPrompt<T> {
+        self.limits.max = Some(max);
+        self
+    }
+
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> MultiSelectPrompt<T> {
+        self.core.output = output;
+        self
+    }
+
+    /// Give up waiting for an answer after some time
+    ///
+    /// When it expires the selected options are submitted, if there are
+    /// as many as `with_min` and `with_max` allow. Otherwise running the
+    /// prompt fails with `PromptError::Timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> MultiSelectPrompt<T> {
+        self.core.timeout = Some(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> MultiSelectPrompt<T> {
+        self.core.countdown = true;
+        self
+    }
+
+    /// Capture the mouse, so options can be clicked and scrolled through
+    ///
+    /// Click an option to highlight and select or deselect it.
+    pub fn with_mouse(mut self) -> MultiSelectPrompt<T> {
+        self.core.mouse = true;
+        self
+    }
+}
+impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<Vec<T>>
+    for MultiSelectPrompt<T>
+{
+    fn line_question(&self) -> Option<String> {
+        Some(self.message.clone())
+    }
+    /// Accepts a comma-separated list of the texts of options, or their (zero-based) indices
+    ///
+    /// An empty line submits the initially selected options.
+    fn parse_line(&mut self, line: &str) -> std::result::Result<Vec<T>, String> {
+        self.selected = parse_selection(line, &self.choices, &self.selected, &self.limits)?;
+        Ok(self.result())
+    }
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        self.limits.check(&self.choices, &self.selected)
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    /// Submits the selected options, if there are as many as the limits allow
+    fn submit_default(&mut self) -> bool {
+        self.limits.check_count(self.selected.len()).is_ok()
+    }
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        self.limits.check_count(self.selected.len())
+    }
+    fn result(&self) -> Vec<T> {
+        self.selected
+            .iter()
+            .map(|&i| self.choices[i].clone())
+            .collect()
+    }
+    fn display(&mut self) -> crossterm::Result<()> {
+        if self.core.state == PromptState::Created {
+            queue!(self.core.output, cursor::Hide)?;
+            self.core.state = PromptState::Running;
+        }
+
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        frame.print(" ");
+        frame.print_styled(print_input_icon(&self.core.state).0);
+        if !self.core.state.is_done() {
+            frame.print(format!(
+                "{}/{} selected",
+                self.selected.len(),
+                self.choices.len()
+            ));
+            frame.print_styled(
+                style(" · Space to toggle, a/n/i to select all/none/invert").with(Color::DarkGrey),
+            );
+            let (choices, selected) = (&self.choices, &self.selected);
+            self.list.print(
+                &mut frame,
+                self.core.output.size(),
+                error_rows(&self.core.error, self.core.output.size().0),
+                choices.len(),
+                self.current,
+                |i| ListEntry {
+                    marker: Some(radio(selected.contains(&i))),
+                    text: choices[i].to_string(),
+                },
+            );
+            print_error(&mut frame, &self.core.error);
+        }
+        if self.core.state == PromptState::Success {
+            print_selected(&mut frame, &self.choices, &self.selected);
+        }
+        if self.core.state.is_done() {
+            frame.new_line();
+            queue!(self.core.output, cursor::Show)?;
+        }
+        self.core.render(&frame)
+    }
+    fn handle_key_event(&mut self, event: KeyEvent) {
+        if is_abort_event(event) {
+            self.core.state = PromptState::Aborted;
+            return;
+        }
+        if event.modifiers == KeyModifiers::empty() {
+            match event.code {
+                KeyCode::Enter => self.core.state = PromptState::Validate,
+                KeyCode::Char(' ') => toggle(&mut self.selected, self.current),
+                KeyCode::Char('a') => self.selected = (0..self.choices.len()).collect(),
+                KeyCode::Char('n') => self.selected.clear(),
+                KeyCode::Char('i') => {
+                    self.selected = (0..self.choices.len())
+                        .filter(|i| !self.selected.contains(i))
+                        .collect()
+                }
+                KeyCode::Home => {
+                    self.current = 0;
+                }
+                KeyCode::End => {
+                    self.current = self.choices.len() - 1;
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.current = self.current.saturating_sub(1);
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.current = cmp::min(self.current + 1, self.choices.len() - 1);
+                }
+                _ => {}
+            }
+        }
+    }
+    fn handle_mouse_event(&mut self, event: MouseEvent) {
+        match event {
+            MouseEvent::Down(MouseButton::Left, _, row, _) => {
+                let line = self.core.line_at(row);
+                if let Some(index) = line.and_then(|line| self.list.clicked_entry(line)) {
+                    self.current = index;
+                    toggle(&mut self.selected, index);
+                }
+            }
+            MouseEvent::ScrollUp(..) => {
+                self.current = self.current.saturating_sub(1);
+            }
+            MouseEvent::ScrollDown(..) => {
+                self.current = cmp::min(self.current + 1, self.choices.len() - 1);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Filter function that decides whether a choice matches the input
+type Filter<T> = Box<dyn Fn(&str, &T) -> bool + Send>;
+
+/// Interactive prompt where the user chooses any number of options from a filterable list
+///
+/// Shows a list of options. Type anything to filter the list, use <kbd>up</kbd>/<kbd>down</kbd>
+/// to navigate, <kbd>space</kbd> or <kbd>tab</kbd> to select or deselect an option and
+/// <kbd>enter</kbd> to submit. Options stay selected when they are filtered out.
+/// The default filter will simply check the choices start with the input's .to_string(),
+/// use `with_filter` to filter differently.
+/// Use `with_min` and `with_max` to limit how many options can be submitted.
+/// The data vector can have a custom type but it must implement
+/// `std::fmt::Display` as well as `std::clone::Clone` and `std::marker::Send`.
+///
+/// # Examples
+///
+/// ```
+/// use prompts::{error::PromptError, Prompt, multiselect::AutocompleteMultiSelectPrompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
+///
+/// let data = vec!["serde", "serde_json", "tokio", "rayon", "clap"];
+/// let mut prompt = AutocompleteMultiSelectPrompt::new("Which crates?", data);
+///
+/// match prompt.run().await {
+///     Ok(crates) => println!("You chose: {:?}", crates),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
+/// }
+/// # }
+/// ```
+pub struct AutocompleteMultiSelectPrompt<T> {
+    message: String,
+    core: PromptCore,
+    choices: Vec<T>,
+    selected: BTreeSet<usize>,
+    current: usize,
+    list: ListView,
+    limits: Limits,
+    input: TextInput,
+    filter: Option<Filter<T>>,
+}
+impl<T: std::fmt::Debug> fmt::Debug for AutocompleteMultiSelectPrompt<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("AutocompleteMultiSelectPrompt")
+            .field("message", &self.message)
+            .field("choices", &self.choices)
+            .field("selected", &self.selected)
+            .field("min", &self.limits.min)
+            .field("max", &self.limits.max)
+            .field(
+                "filter",
+                &format_args!(
+                    "{}",
+                    &match self.filter {
+                        Some(_) => "custom filter",
+                        None => "simple filter",
+                    }
+                ),
+            )
+            .finish()
+    }
+}
+impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display>
+    AutocompleteMultiSelectPrompt<T>
+{
+    /// Returns a AutocompleteMultiSelectPrompt ready to be run
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to display to the user before the prompt
+    /// * `choices` - A vector of options that the user can choose from
+    pub fn new<S>(message: S, choices: Vec<T>) -> AutocompleteMultiSelectPrompt<T>
+    where
+        S: Into<String>,
+    {
+        AutocompleteMultiSelectPrompt {
+            message: message.into(),
+            choices,
+            core: PromptCore::default(),
+            selected: BTreeSet::new(),
+            current: 0,
+            list: ListView::new(10),
+            limits: Limits::default(),
+            input: TextInput::default(),
+            filter: None,
+        }
+    }
+
+    /// Set which options are selected initially
+    ///
+    /// # Arguments
+    ///
+    /// * `selected` - The (zero-based) indices of the selected options
+    pub fn set_selected(mut self, selected: Vec<usize>) -> AutocompleteMultiSelectPrompt<T> {
+        self.selected = selected.into_iter().collect();
+        self
+    }
+
+    /// Set how many options must be selected at least
+    pub fn with_min(mut self, min: usize) -> AutocompleteMultiSelectPrompt<T> {
+        self.limits.min = min;
+        self
+    }
+
+    /// Set how many options can be selected at most
+    pub fn with_max(mut self, max: usize) -> AutocompleteMultiSelectPrompt<T> {
+        self.limits.max = Some(max);
+        self
+    }
+
+    /// Provide a custom filter closure
+    ///
+    /// Unlike `AutocompletePrompt::with_filter` it is called for each choice on its own,
+    /// so options can stay selected while they are filtered out.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - Filter closure that accepts the input and a choice,
+    ///   and returns whether the choice matches the input.
+    pub fn with_filter<F>(mut self, filter: F) -> AutocompleteMultiSelectPrompt<T>
+    where
+        F: Fn(&str, &T) -> bool + Send + 'static,
+    {
+        self.filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> AutocompleteMultiSelectPrompt<T> {
+        self.core.output = output;
+        self
+    }
+
+    /// Give up waiting for an answer after some time
+    ///
+    /// When it expires the selected options are submitted, if there are
+    /// as many as `with_min` and `with_max` allow. Otherwise running the
+    /// prompt fails with `PromptError::Timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> AutocompleteMultiSelectPrompt<T> {
+        self.core.timeout = Some(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> AutocompleteMultiSelectPrompt<T> {
+        self.core.countdown = true;
+        self
+    }
+
+    /// Capture the mouse, so options can be clicked and scrolled through
+    ///
+    /// Click an option to highlight and select or deselect it.
+    pub fn with_mouse(mut self) -> AutocompleteMultiSelectPrompt<T> {
+        self.core.mouse = true;
+        self
+    }
+
+    /// Returns the indices of the choices that match the input
+    fn filtered_indices(&self) -> Vec<usize> {
+        let input = self.input.as_str();
+        (0..self.choices.len())
+            .filter(|&i| match &self.filter {
+                Some(filter) => filter(input, &self.choices[i]),
+                None => self.choices[i].to_string().starts_with(input),
+            })
+            .collect()
+    }
+}
+impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<Vec<T>>
+    for AutocompleteMultiSelectPrompt<T>
+{
+    fn line_question(&self) -> Option<String> {
+        Some(self.message.clone())
+    }
+    /// Accepts a comma-separated list of the texts of options, or their (zero-based) indices
+    ///
+    /// An empty line submits the initially selected options.
+    fn parse_line(&mut self, line: &str) -> std::result::Result<Vec<T>, String> {
+        self.selected = parse_selection(line, &self.choices, &self.selected, &self.limits)?;
+        Ok(self.result())
+    }
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        self.limits.check(&self.choices, &self.selected)
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    /// Submits the selected options, if there are as many as the limits allow
+    fn submit_default(&mut self) -> bool {
+        self.limits.check_count(self.selected.len()).is_ok()
+    }
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        self.limits.check_count(self.selected.len())
+    }
+    fn result(&self) -> Vec<T> {
+        self.selected
+            .iter()
+            .map(|&i| self.choices[i].clone())
+            .collect()
+    }
+    fn display(&mut self) -> crossterm::Result<()> {
+        let filtered = self.filtered_indices();
+
+        self.current = cmp::min(self.current, filtered.len().saturating_sub(1));
+
+        if self.core.state == PromptState::Created {
+            self.core.state = PromptState::Running;
+        }
+
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        frame.print(" ");
+        frame.print_styled(print_input_icon(&self.core.state).0);
+        if !self.core.state.is_done() {
+            frame.print(self.input.before_cursor());
+            frame.set_cursor();
+            frame.print(self.input.after_cursor());
+            frame.print_styled(
+                style(format!(
+                    "  {}/{} selected · Space or Tab to toggle",
+                    self.selected.len(),
+                    self.choices.len()
+                ))
+                .with(Color::DarkGrey),
+            );
+            if filtered.is_empty() {
+                frame.new_line();
+                frame.print_styled(style("Nothing matched your search").with(Color::DarkGrey));
+            }
+            let (choices, selected) = (&self.choices, &self.selected);
+            self.list.print(
+                &mut frame,
+                self.core.output.size(),
+                error_rows(&self.core.error, self.core.output.size().0),
+                filtered.len(),
+                self.current,
+                |i| ListEntry {
+                    marker: Some(radio(selected.contains(&filtered[i]))),
+                    text: choices[filtered[i]].to_string(),
+                },
+            );
+            print_error(&mut frame, &self.core.error);
+        }
+        if self.core.state == PromptState::Success {
+            print_selected(&mut frame, &self.choices, &self.selected);
+        }
+        if self.core.state.is_done() {
+            frame.new_line();
+        }
+        self.core.render(&frame)
+    }
+    fn handle_key_event(&mut self, event: KeyEvent) {
+        if is_abort_event(event) {
+            self.core.state = PromptState::Aborted;
+            return;
+        }
+        if event.modifiers == KeyModifiers::empty() {
+            match event.code {
+                KeyCode::Enter => self.core.state = PromptState::Validate,
+                KeyCode::Char(' ') | KeyCode::Tab => {
+                    if let Some(&index) = self.filtered_indices().get(self.current) {
+                        toggle(&mut self.selected, index);
+                    }
+                }
+                KeyCode::Home => {
+                    self.current = 0;
+                }
+                KeyCode::End => {
+                    self.current = self.filtered_indices().len().saturating_sub(1);
+                }
+                KeyCode::Up => {
+                    self.current = self.current.saturating_sub(1);
+                }
+                KeyCode::Down => {
+                    let matches = self.filtered_indices().len();
+                    self.current = cmp::min(self.current + 1, matches.saturating_sub(1));
+                }
+                KeyCode::Backspace => self.input.backspace(),
+                KeyCode::Delete => self.input.delete(),
+                KeyCode::Left => self.input.move_left(),
+                KeyCode::Right => self.input.move_right(),
+                KeyCode::Char(c) => self.input.insert(c),
+                _ => {}
+            }
+        }
+    }
+    fn handle_mouse_event(&mut self, event: MouseEvent) {
+        let filtered = self.filtered_indices();
+        match event {
+            MouseEvent::Down(MouseButton::Left, _, row, _) => {
+                let line = self.core.line_at(row);
+                if let Some(i) = line.and_then(|line| self.list.clicked_entry(line)) {
+                    self.current = i;
+                    toggle(&mut self.selected, filtered[i]);
+                }
+            }
+            MouseEvent::ScrollUp(..) => {
+                self.current = self.current.saturating_sub(1);
+            }
+            MouseEvent::ScrollDown(..) => {
+                self.current = cmp::min(self.current + 1, filtered.len().saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::VirtualTerminal,
+        utils::{key_events, typed},
+    };
+    use crossterm::event::Event;
+    use std::io::sink;
+
+    fn toppings() -> Vec<&'static str> {
+        vec!["cheese", "ham", "olives", "pineapple", "basil"]
+    }
+
+    #[test]
+    fn run_toggles_with_space() {
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![0, 1])
+            .with_output(Output::new(sink()));
+        let events = vec![
+            KeyEvent::from(KeyCode::Down),
+            KeyEvent::from(KeyCode::Char(' ')),
+            KeyEvent::from(KeyCode::End),
+            KeyEvent::from(KeyCode::Char(' ')),
+            KeyEvent::from(KeyCode::Enter),
+        ];
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), vec!["cheese", "basil"]);
+    }
+
+    #[test]
+    fn display_selected_and_scrolled() {
+        let terminal = VirtualTerminal::new(80, 4);
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![3])
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+        for _ in 0..3 {
+            prompt.handle_key_event(KeyEvent::from(KeyCode::Down));
+        }
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(
+            screen.text(),
+            "? Toppings? › 1/5 selected · Space to toggle, a/n/i to select all/none/invert\n  ↑ ◯ olives\n❯   ◉ pineapple\n    ◯ basil"
+        );
+        assert_eq!(screen.cell(4, 2).style.foreground, Some(Color::Green));
+        assert!(!screen.cursor_visible);
+    }
+
+    #[test]
+    fn display_answer_on_success() {
+        let terminal = VirtualTerminal::new(50, 10);
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![2, 0])
+            .with_output(terminal.output());
+        let events = vec![KeyEvent::from(KeyCode::Enter)];
+        assert!(prompt.run_blocking_with_events(key_events(events)).is_ok());
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "✔ Toppings? … cheese, olives");
+        assert!(screen.cursor_visible);
+    }
+
+    #[test]
+    fn run_keeps_going_until_count_is_valid() {
+        let terminal = VirtualTerminal::new(80, 10);
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .with_min(1)
+            .with_max(3)
+            .with_output(terminal.output());
+        let events = vec![
+            KeyEvent::from(KeyCode::Enter),
+            KeyEvent::from(KeyCode::Char('a')),
+            KeyEvent::from(KeyCode::Enter),
+        ];
+        assert!(prompt.run_blocking_with_events(key_events(events)).is_err());
+        let screen = terminal.screen();
+        assert_eq!(
+            screen.row(0),
+            "? Toppings? › 5/5 selected · Space to toggle, a/n/i to select all/none/invert"
+        );
+        assert_eq!(screen.row(6), "› Select at most 3 options");
+
+        let events = vec![
+            KeyEvent::from(KeyCode::Enter),
+            KeyEvent::from(KeyCode::Char('a')),
+            KeyEvent::from(KeyCode::Char(' ')),
+            KeyEvent::from(KeyCode::Down),
+            KeyEvent::from(KeyCode::Char(' ')),
+            KeyEvent::from(KeyCode::Char('i')),
+            KeyEvent::from(KeyCode::Enter),
+        ];
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), vec!["cheese", "ham"]);
+    }
+
+    #[test]
+    fn display_error_on_short_terminal() {
+        let terminal = VirtualTerminal::new(80, 4);
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .with_min(1)
+            .with_output(terminal.output());
+        let events = vec![
+            KeyEvent::from(KeyCode::Enter),
+            KeyEvent::from(KeyCode::Down),
+            KeyEvent::from(KeyCode::Down),
+            KeyEvent::from(KeyCode::Char(' ')),
+        ];
+        assert!(prompt.run_blocking_with_events(key_events(events)).is_err());
+
+        for frame in terminal.frames() {
+            assert!(frame.row(0).starts_with("? Toppings? › "));
+            assert_eq!(frame.text().matches('❯').count(), 1);
+        }
+        assert_eq!(
+            terminal.screen().text(),
+            "? Toppings? › 1/5 selected · Space to toggle, a/n/i to select all/none/invert\n  ↑ ◯ ham\n❯ ↓ ◉ olives\n› Select at least 1 option"
+        );
+    }
+
+    #[test]
+    fn run_with_mouse() {
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .with_output(Output::new(sink()))
+            .with_mouse();
+        let click = |row| {
+            Ok(Event::Mouse(MouseEvent::Down(
+                MouseButton::Left,
+                4,
+                row,
+                KeyModifiers::empty(),
+            )))
+        };
+        let events = vec![
+            click(2),
+            click(3),
+            click(2),
+            Ok(Event::Key(KeyCode::Enter.into())),
+        ];
+        let result = prompt.run_blocking_with_events(events.into_iter());
+        assert_eq!(result.unwrap(), vec!["olives"]);
+    }
+
+    #[test]
+    fn run_with_mouse_below_multi_line_message() {
+        let mut prompt = MultiSelectPrompt::new("Intro\nToppings?", toppings())
+            .with_output(Output::new(sink()))
+            .with_mouse();
+        let events = vec![
+            Ok(Event::Mouse(MouseEvent::Down(
+                MouseButton::Left,
+                4,
+                2,
+                KeyModifiers::empty(),
+            ))),
+            Ok(Event::Key(KeyCode::Enter.into())),
+        ];
+        let result = prompt.run_blocking_with_events(events.into_iter());
+        assert_eq!(result.unwrap(), vec!["cheese"]);
+    }
+
+    #[test]
+    fn run_with_invalid_selection() {
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![5])
+            .with_output(Output::new(sink()));
+        match prompt.run_blocking_with_events(key_events(Vec::<KeyEvent>::new())) {
+            Err(PromptError::InvalidConfiguration(_)) => {}
+            _ => panic!("expected an InvalidConfiguration error"),
+        }
+        match prompt.run_lines("\r\n".as_bytes()) {
+            Err(PromptError::InvalidConfiguration(_)) => {}
+            _ => panic!("expected an InvalidConfiguration error"),
+        }
+    }
+
+    #[test]
+    fn run_submits_selected_on_timeout() {
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![1])
+            .with_min(1)
+            .with_timeout(Duration::from_secs(0))
+            .with_output(Output::new(sink()));
+        let events = vec![KeyEvent::from(KeyCode::Char(' '))];
+        let result = prompt.run_blocking_with_events(key_events(events.clone()));
+        assert_eq!(result.unwrap(), vec!["ham"]);
+
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![1])
+            .with_min(2)
+            .with_timeout(Duration::from_secs(0))
+            .with_output(Output::new(sink()));
+        match prompt.run_blocking_with_events(key_events(events)) {
+            Err(PromptError::Timeout) => {}
+            _ => panic!("expected a Timeout error"),
+        }
+    }
+
+    #[test]
+    fn run_lines_by_text_or_index() {
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![1])
+            .with_output(Output::new(sink()));
+        let result = prompt.run_lines("anchovies\r\nbasil, 0\r\n".as_bytes());
+        assert_eq!(result.unwrap(), vec!["cheese", "basil"]);
+
+        let result = prompt.run_lines("\r\n".as_bytes());
+        assert_eq!(result.unwrap(), vec!["cheese", "basil"]);
+
+        let mut prompt = MultiSelectPrompt::new("Toppings?", toppings())
+            .with_max(1)
+            .with_output(Output::new(sink()));
+        let result = prompt.run_lines("basil, 0\r\n0\r\n".as_bytes());
+        assert_eq!(result.unwrap(), vec!["cheese"]);
+    }
+
+    #[test]
+    fn autocomplete_keeps_selection_while_filtering() {
+        let mut prompt = AutocompleteMultiSelectPrompt::new("Toppings?", toppings())
+            .with_output(Output::new(sink()));
+        let mut events = typed("p ");
+        events.push(KeyEvent::from(KeyCode::Backspace));
+        events.extend(typed("o"));
+        events.push(KeyEvent::from(KeyCode::Tab));
+        events.push(KeyEvent::from(KeyCode::Backspace));
+        events.push(KeyEvent::from(KeyCode::Down));
+        events.push(KeyEvent::from(KeyCode::Tab));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), vec!["ham", "olives", "pineapple"]);
+    }
+
+    #[test]
+    fn autocomplete_submits_selected_on_timeout() {
+        let mut prompt = AutocompleteMultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![0, 2])
+            .with_max(2)
+            .with_timeout(Duration::from_secs(0))
+            .with_output(Output::new(sink()));
+        let events = vec![KeyEvent::from(KeyCode::Char('h'))];
+        let result = prompt.run_blocking_with_events(key_events(events.clone()));
+        assert_eq!(result.unwrap(), vec!["cheese", "olives"]);
+
+        let mut prompt = AutocompleteMultiSelectPrompt::new("Toppings?", toppings())
+            .with_min(1)
+            .with_timeout(Duration::from_secs(0))
+            .with_output(Output::new(sink()));
+        match prompt.run_blocking_with_events(key_events(events)) {
+            Err(PromptError::Timeout) => {}
+            _ => panic!("expected a Timeout error"),
+        }
+    }
+
+    #[test]
+    fn autocomplete_display_filtered() {
+        let terminal = VirtualTerminal::new(80, 10);
+        let mut prompt = AutocompleteMultiSelectPrompt::new("Toppings?", toppings())
+            .set_selected(vec![0, 3])
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+        for event in typed("pi") {
+            prompt.handle_key_event(event);
+        }
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(
+            screen.text(),
+            "? Toppings? › pi  2/5 selected · Space or Tab to toggle\n❯   ◉ pineapple"
+        );
+        assert_eq!(screen.cursor, (16, 0));
+
+        for event in typed("e") {
+            prompt.handle_key_event(event);
+        }
+        prompt.display().unwrap();
+        assert_eq!(
+            terminal.screen().text(),
+            "? Toppings? › pie  2/5 selected · Space or Tab to toggle\nNothing matched your search"
+        );
+    }
+
+    #[test]
+    fn autocomplete_with_custom_filter_and_max() {
+        let terminal = VirtualTerminal::new(80, 10);
+        let mut prompt = AutocompleteMultiSelectPrompt::new("Toppings?", toppings())
+            .with_filter(|input, choice| choice.contains(input))
+            .with_max(1)
+            .with_output(terminal.output());
+        let mut events = typed("s");
+        events.push(KeyEvent::from(KeyCode::Tab));
+        events.push(KeyEvent::from(KeyCode::Down));
+        events.push(KeyEvent::from(KeyCode::Tab));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        assert!(prompt.run_blocking_with_events(key_events(events)).is_err());
+        let screen = terminal.screen();
+        assert_eq!(screen.row(2), "❯   ◉ olives");
+        assert_eq!(screen.row(3), "    ◯ basil");
+        assert_eq!(screen.row(4), "› Select at most 1 option");
+
+        let events = vec![KeyEvent::from(KeyCode::Tab), KeyEvent::from(KeyCode::Enter)];
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), vec!["cheese"]);
+    }
+}
@@ -0,0 +1,452 @@
+//! Interactive prompt where the user enters a number
+
+use crate::{
+    error::PromptError,
+    utils::{
+        is_abort_event, print_input_icon, print_state_icon, Figures, Frame, Output, PromptCore,
+        PromptState, TextInput,
+    },
+    Prompt,
+};
+use crossterm::{
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    style::{style, Attribute, Color},
+};
+use std::fmt;
+use std::time::Duration;
+
+/// Interactive prompt where the user enters a number
+///
+/// Only accepts numeric input. Use <kbd>up</kbd>/<kbd>down</kbd> to increment
+/// and decrement the number by a step, and <kbd>enter</kbd> to submit.
+/// Numbers are clamped between the minimum and maximum, and are whole
+/// numbers unless floats are enabled with `with_float`.
+/// If default/initial is set, it is shown until the user types and
+/// <kbd>enter</kbd> submits it, as does running out of time if a timeout is set.
+///
+/// # Examples
+///
+/// ```
+/// use prompts::{error::PromptError, number::NumberPrompt, Prompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
+/// let mut prompt = NumberPrompt::new("How many workers?")
+///     .with_min(1.0)
+///     .with_max(64.0)
+///     .set_initial(4.0);
+///
+/// match prompt.run().await {
+///     Ok(n) => println!("Starting {} workers", n),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
+/// }
+/// # }
+/// ```
+pub struct NumberPrompt {
+    message: String,
+    core: PromptCore,
+    input: TextInput,
+    answer: f64,
+    initial: Option<f64>,
+    min: f64,
+    max: f64,
+    step: f64,
+    float: bool,
+    precision: usize,
+}
+impl fmt::Debug for NumberPrompt {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("NumberPrompt")
+            .field("message", &self.message)
+            .field("initial", &self.initial)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("step", &self.step)
+            .field("float", &self.float)
+            .field("precision", &self.precision)
+            .finish()
+    }
+}
+impl Default for NumberPrompt {
+    fn default() -> NumberPrompt {
+        NumberPrompt {
+            message: String::new(),
+            core: PromptCore::default(),
+            input: TextInput::default(),
+            answer: 0.0,
+            initial: None,
+            min: f64::NEG_INFINITY,
+            max: f64::INFINITY,
+            step: 1.0,
+            float: false,
+            precision: 2,
+        }
+    }
+}
+impl NumberPrompt {
+    /// Returns a NumberPrompt ready to be run
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to display to the user before the prompt
+    pub fn new<S>(message: S) -> NumberPrompt
+    where
+        S: Into<String>,
+    {
+        NumberPrompt {
+            message: message.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set default/initial answer
+    pub fn set_initial(mut self, initial: f64) -> NumberPrompt {
+        self.initial = Some(initial);
+        self
+    }
+
+    /// Set the smallest number that can be entered
+    pub fn with_min(mut self, min: f64) -> NumberPrompt {
+        self.min = min;
+        self
+    }
+
+    /// Set the largest number that can be entered
+    pub fn with_max(mut self, max: f64) -> NumberPrompt {
+        self.max = max;
+        self
+    }
+
+    /// Set how much <kbd>up</kbd>/<kbd>down</kbd> change the number by, 1 by default
+    pub fn with_step(mut self, step: f64) -> NumberPrompt {
+        self.step = step;
+        self
+    }
+
+    /// Accept numbers with decimals, instead of only whole numbers
+    ///
+    /// # Arguments
+    ///
+    /// * `precision` - Number of decimals to round answers to
+    pub fn with_float(mut self, precision: usize) -> NumberPrompt {
+        self.float = true;
+        self.precision = precision;
+        self
+    }
+
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> NumberPrompt {
+        self.core.output = output;
+        self
+    }
+
+    /// Give up waiting for an answer after some time
+    ///
+    /// When it expires the initial answer is submitted, see `set_initial`,
+    /// even if another number was typed. Without one, running the prompt
+    /// fails with `PromptError::Timeout`.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> NumberPrompt {
+        self.core.timeout = Some(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> NumberPrompt {
+        self.core.countdown = true;
+        self
+    }
+
+    /// Rounds the number to the precision and clamps it between min and max
+    fn round(&self, number: f64) -> f64 {
+        let rounded = if self.float {
+            let factor = 10f64.powi(self.precision as i32);
+            (number * factor).round() / factor
+        } else {
+            number.round()
+        };
+        // Adding zero turns -0 into 0
+        rounded.max(self.min).min(self.max) + 0.0
+    }
+
+    /// Parses an answer, falling back to the initial answer if it is empty
+    fn parse(&self, text: &str) -> std::result::Result<f64, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return self
+                .initial
+                .map(|initial| self.round(initial))
+                .ok_or_else(|| "Please enter a number".to_string());
+        }
+        match text.parse::<f64>() {
+            Ok(number) if !number.is_finite() => Err("Please enter a number".to_string()),
+            Ok(number) if !self.float && number.fract() != 0.0 => {
+                Err("Please enter a whole number".to_string())
+            }
+            Ok(number) => Ok(self.round(number)),
+            Err(_) => Err("Please enter a number".to_string()),
+        }
+    }
+
+    /// Increments the number by `step` times the given amount
+    ///
+    /// Without a number typed, steps from the initial answer, or starts at zero if there is none.
+    fn increment(&mut self, steps: f64) {
+        let number = match self.input.as_str().parse::<f64>().ok().or(self.initial) {
+            Some(number) => number + self.step * steps,
+            None => 0.0,
+        };
+        self.input = TextInput::new(self.round(number).to_string());
+    }
+
+    /// Whether the character can be typed at the cursor
+    fn accepts(&self, c: char) -> bool {
+        match c {
+            '0'..='9' => true,
+            '-' => {
+                self.min < 0.0
+                    && self.input.before_cursor().is_empty()
+                    && !self.input.as_str().contains('-')
+            }
+            '.' => self.float && !self.input.as_str().contains('.'),
+            _ => false,
+        }
+    }
+}
+impl Prompt<f64> for NumberPrompt {
+    fn line_question(&self) -> Option<String> {
+        Some(self.message.clone())
+    }
+    /// Accepts a number within min and max, or an empty line for the initial value
+    fn parse_line(&mut self, line: &str) -> std::result::Result<f64, String> {
+        self.answer = self.parse(line)?;
+        Ok(self.answer)
+    }
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        if self.min.is_nan() || self.max.is_nan() {
+            return Err(PromptError::InvalidConfiguration(
+                "the minimum or maximum is not a number".to_string(),
+            ));
+        }
+        // Infinite bounds mean there is no bound
+        let fractional = |bound: f64| bound.is_finite() && bound.fract() != 0.0;
+        if !self.float && (fractional(self.min) || fractional(self.max)) {
+            return Err(PromptError::InvalidConfiguration(format!(
+                "the minimum {} or maximum {} is not a whole number, but floats are not enabled",
+                self.min, self.max
+            )));
+        }
+        if self.min > self.max {
+            return Err(PromptError::InvalidConfiguration(format!(
+                "the minimum {} is larger than the maximum {}",
+                self.min, self.max
+            )));
+        }
+        if self.step.is_nan() || self.step <= 0.0 {
+            return Err(PromptError::InvalidConfiguration(format!(
+                "the step {} is not a positive number",
+                self.step
+            )));
+        }
+        if !self.float && self.step.fract() != 0.0 {
+            return Err(PromptError::InvalidConfiguration(format!(
+                "the step {} is not a whole number, but floats are not enabled",
+                self.step
+            )));
+        }
+        Ok(())
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    /// Submits the initial answer, if there is one
+    fn submit_default(&mut self) -> bool {
+        match self.initial {
+            Some(initial) => {
+                self.answer = self.round(initial);
+                true
+            }
+            None => false,
+        }
+    }
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        self.answer = self.parse(self.input.as_str())?;
+        Ok(())
+    }
+    fn result(&self) -> f64 {
+        self.answer
+    }
+    fn display(&mut self) -> crossterm::Result<()> {
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        frame.print(" ");
+        frame.print_styled(print_input_icon(&self.core.state).0);
+        if !self.core.state.is_done() {
+            frame.print(self.input.before_cursor());
+            frame.set_cursor();
+            frame.print(self.input.after_cursor());
+            if let (Some(initial), "") = (self.initial, self.input.as_str()) {
+                frame.print_styled(style(self.round(initial).to_string()).with(Color::DarkGrey));
+            }
+            if let Some(msg) = &self.core.error {
+                frame.new_line();
+                frame.print(format!("{} ", Figures::PointerSmall.as_str()));
+                frame.print_styled(style(msg).with(Color::Red).attribute(Attribute::Italic));
+            }
+        }
+        if self.core.state == PromptState::Success {
+            frame.print(self.answer);
+        }
+        if self.core.state.is_done() {
+            frame.new_line();
+        }
+        self.core.render(&frame)
+    }
+    fn handle_key_event(&mut self, event: KeyEvent) {
+        if is_abort_event(event) {
+            self.core.state = PromptState::Aborted;
+            return;
+        }
+        if event.modifiers == KeyModifiers::empty() {
+            match event.code {
+                KeyCode::Enter => self.core.state = PromptState::Validate,
+                KeyCode::Up => self.increment(1.0),
+                KeyCode::Down => self.increment(-1.0),
+                KeyCode::Backspace => self.input.backspace(),
+                KeyCode::Delete => self.input.delete(),
+                KeyCode::Left => self.input.move_left(),
+                KeyCode::Right => self.input.move_right(),
+                KeyCode::Home => self.input.move_home(),
+                KeyCode::End => self.input.move_end(),
+                KeyCode::Char(c) if self.accepts(c) => self.input.insert(c),
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::VirtualTerminal,
+        utils::{key_events, typed},
+    };
+    use std::io::sink;
+
+    #[test]
+    fn run_with_invalid_configuration() {
+        let prompts = vec![
+            NumberPrompt::new("Workers?").with_min(10.0).with_max(1.0),
+            NumberPrompt::new("Workers?").with_step(0.0),
+            NumberPrompt::new("Workers?").with_step(-1.0),
+            NumberPrompt::new("Workers?").with_step(f64::NAN),
+            NumberPrompt::new("Workers?").with_step(0.5),
+            NumberPrompt::new("Workers?").with_min(f64::NAN),
+            NumberPrompt::new("Workers?").with_max(f64::NAN),
+            NumberPrompt::new("Workers?").with_min(0.5),
+            NumberPrompt::new("Workers?").with_max(9.5),
+        ];
+        for prompt in prompts {
+            let mut prompt = prompt.with_output(Output::new(sink()));
+            match prompt.run_blocking_with_events(key_events(vec![KeyCode::Enter])) {
+                Err(PromptError::InvalidConfiguration(_)) => {}
+                _ => panic!("expected an InvalidConfiguration error for {:?}", prompt),
+            }
+        }
+
+        let mut prompt = NumberPrompt::new("Ratio?")
+            .with_min(0.5)
+            .with_step(0.5)
+            .with_float(1)
+            .with_output(Output::new(sink()));
+        assert_eq!(prompt.run_lines("0\n".as_bytes()).unwrap(), 0.5);
+    }
+
+    #[test]
+    fn run_steps_with_arrow_keys() {
+        let mut prompt = NumberPrompt::new("Workers?")
+            .with_min(0.0)
+            .with_max(10.0)
+            .with_step(4.0)
+            .with_output(Output::new(sink()));
+        let events = vec![
+            KeyCode::Up,
+            KeyCode::Up,
+            KeyCode::Up,
+            KeyCode::Up,
+            KeyCode::Down,
+            KeyCode::Enter,
+        ];
+        assert_eq!(
+            prompt.run_blocking_with_events(key_events(events)).unwrap(),
+            6.0
+        );
+    }
+
+    #[test]
+    fn run_ignores_non_numeric_keys() {
+        let mut prompt = NumberPrompt::new("Workers?").with_output(Output::new(sink()));
+        let mut events = typed("-1a.2e");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        assert_eq!(
+            prompt.run_blocking_with_events(key_events(events)).unwrap(),
+            -12.0
+        );
+    }
+
+    #[test]
+    fn run_rounds_floats_to_precision() {
+        let mut prompt = NumberPrompt::new("Ratio?")
+            .with_float(2)
+            .with_step(0.1)
+            .with_output(Output::new(sink()));
+        let events = vec![
+            KeyCode::Char('0'),
+            KeyCode::Char('.'),
+            KeyCode::Char('2'),
+            KeyCode::Up,
+            KeyCode::Enter,
+        ];
+        assert_eq!(
+            prompt.run_blocking_with_events(key_events(events)).unwrap(),
+            0.3
+        );
+    }
+
+    #[test]
+    fn display_initial_and_answer() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = NumberPrompt::new("Workers?")
+            .set_initial(4.0)
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Workers? › 4");
+        assert_eq!(screen.cell(13, 0).style.foreground, Some(Color::DarkGrey));
+        assert_eq!(screen.cursor, (13, 0));
+
+        let result = prompt.run_blocking_with_events(key_events(vec![KeyCode::Up, KeyCode::Enter]));
+        assert_eq!(result.unwrap(), 5.0);
+        assert_eq!(terminal.screen().text(), "✔ Workers? … 5");
+    }
+
+    #[test]
+    fn run_lines_rejects_invalid_numbers() {
+        let mut prompt = NumberPrompt::new("Workers?")
+            .with_max(8.0)
+            .with_output(Output::new(sink()));
+        let result = prompt.run_lines("many\r\n2.5\r\n12\r\n".as_bytes());
+        assert_eq!(result.unwrap(), 8.0);
+    }
+}
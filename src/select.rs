@@ -1,23 +1,23 @@
 //! Interactive prompt where the user chooses from a list of options
 
 use crate::{
+    error::PromptError,
     utils::{
-        calc_entries, is_abort_event, print_input_icon, print_state_icon, Figures, PromptState,
+        find_choice, is_abort_event, print_input_icon, print_state_icon, Clicks, Frame, ListEntry,
+        ListView, Output, PromptCore, PromptState,
     },
     Prompt,
 };
-use async_trait::async_trait;
 use crossterm::{
     cursor,
-    event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
+    event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent},
     queue,
-    style::{style, Attribute, Color, Print, PrintStyledContent},
-    terminal::{disable_raw_mode, enable_raw_mode, size as terminal_size, Clear, ClearType},
+    style::{style, Attribute},
 };
-use futures::StreamExt;
 use std::cmp;
 use std::fmt;
-use std::io::{stdout, Write};
+use std::io::Write;
+use std::time::Duration;
 
 /// Interactive prompt where the user chooses from a list of options
 ///
@@ -32,23 +32,27 @@ use std::io::{stdout, Write};
 /// # Examples
 ///
 /// ```
-/// use prompt::{Prompt, select::{SelectPrompt}};
+/// use prompts::{error::PromptError, Prompt, select::{SelectPrompt}};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
 ///
 /// let data = vec!["The", "quick", "brown", "fox", "jumps", "over", "the", "lazy", "dog"];
 /// let mut prompt = SelectPrompt::new("Choose a word", data);
 ///
 /// match prompt.run().await {
-///     Ok(Some(s)) => println!("Your choice is: {}", s),
-///     Ok(None) => println!("Prompt was aborted!"),
-///     Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+///     Ok(s) => println!("Your choice is: {}", s),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
 /// }
+/// # }
 /// ```
 pub struct SelectPrompt<T> {
     message: String,
-    state: PromptState,
+    core: PromptCore,
     choices: Vec<T>,
     current: usize,
-    limit: usize,
+    list: ListView,
+    clicks: Clicks,
 }
 impl<T: std::fmt::Debug> fmt::Debug for SelectPrompt<T> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
@@ -72,125 +76,117 @@ impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> SelectPrompt<
         SelectPrompt {
             message: message.into(),
             choices,
-            state: PromptState::default(),
+            core: PromptCore::default(),
             current: 0,
-            limit: 10,
+            list: ListView::new(10),
+            clicks: Clicks::default(),
         }
     }
-}
-#[async_trait]
-impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<T> for SelectPrompt<T> {
-    /// Runs the prompt
+
+    /// Render the prompt somewhere other than stdout
     ///
-    /// Stops either when the user selects an option, an error occurs,
-    /// or the prompt is aborted by the user using CTRL+c, CTRL+z or ESC.
-    async fn run(&mut self) -> std::result::Result<Option<T>, crossterm::ErrorKind> {
-        enable_raw_mode()?;
-        let mut reader = EventStream::new();
-
-        self.display()?;
-
-        loop {
-            match reader.next().await {
-                Some(Ok(Event::Key(event))) => self.handle_key_event(event),
-                Some(Err(e)) => {
-                    disable_raw_mode()?;
-                    return Err(e);
-                }
-                _ => {}
-            }
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> SelectPrompt<T> {
+        self.core.output = output;
+        self
+    }
+
+    /// Give up waiting for an answer after some time
+    ///
+    /// The highlighted choice is not submitted, running the prompt
+    /// fails with `PromptError::Timeout` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> SelectPrompt<T> {
+        self.core.timeout = Some(timeout);
+        self
+    }
 
-            self.display()?;
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> SelectPrompt<T> {
+        self.core.countdown = true;
+        self
+    }
 
-            match self.state {
-                PromptState::Aborted => {
-                    disable_raw_mode()?;
-                    return Ok(None);
-                }
-                PromptState::Success => {
-                    disable_raw_mode()?;
-                    return Ok(Some(self.choices[self.current].clone()));
-                }
-                _ => (),
-            }
+    /// Capture the mouse, so choices can be clicked and scrolled through
+    ///
+    /// Click a choice to highlight it, double-click to submit it.
+    pub fn with_mouse(mut self) -> SelectPrompt<T> {
+        self.core.mouse = true;
+        self
+    }
+}
+impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<T> for SelectPrompt<T> {
+    fn line_question(&self) -> Option<String> {
+        Some(self.message.clone())
+    }
+    /// Accepts the text of a choice, or its (zero-based) index
+    fn parse_line(&mut self, line: &str) -> std::result::Result<T, String> {
+        let index = find_choice(&self.choices, line)?;
+        Ok(self.choices[index].clone())
+    }
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        if self.choices.is_empty() {
+            return Err(PromptError::InvalidConfiguration(
+                "there are no choices to choose from".to_string(),
+            ));
         }
+        Ok(())
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    fn result(&self) -> T {
+        self.choices[self.current].clone()
     }
     fn display(&mut self) -> crossterm::Result<()> {
-        let mut stdout = stdout();
-
-        let (start_index, end_index) = calc_entries(
-            self.current,
-            self.choices.len(),
-            cmp::min(self.limit, (terminal_size()?.1 - 1) as usize),
-        );
+        if self.core.state == PromptState::Created {
+            queue!(self.core.output, cursor::Hide)?;
+            self.core.state = PromptState::Running;
+        }
 
-        if self.state == PromptState::Created {
-            queue!(stdout, cursor::Hide)?;
-            self.state = PromptState::Running;
-        } else {
-            queue!(
-                stdout,
-                cursor::MoveUp((end_index - start_index) as u16),
-                cursor::MoveToColumn(0),
-                Clear(ClearType::FromCursorDown)
-            )?;
-        }
-
-        queue!(
-            stdout,
-            print_state_icon(&self.state),
-            Print(" "),
-            PrintStyledContent(style(&self.message).attribute(Attribute::Bold))
-        )?;
-        if !self.state.is_done() {
-            for i in start_index..end_index {
-                let choice = self.choices[i].to_string();
-                let prefix = if i == start_index && start_index > 0 {
-                    Figures::ArrowUp.as_str()
-                } else if i == end_index - 1 && end_index < self.choices.len() {
-                    Figures::ArrowDown.as_str()
-                } else {
-                    " "
-                };
-                queue!(
-                    stdout,
-                    Print("\n\r"),
-                    PrintStyledContent(if i == self.current {
-                        style(Figures::Pointer.as_str()).with(Color::Cyan)
-                    } else {
-                        style(" ")
-                    }),
-                    Print(format!(" {} ", prefix)),
-                    PrintStyledContent(if i == self.current {
-                        style(choice).attribute(Attribute::Bold).with(Color::Cyan)
-                    } else {
-                        style(choice)
-                    }),
-                )?;
-            }
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        if !self.core.state.is_done() {
+            let choices = &self.choices;
+            self.list.print(
+                &mut frame,
+                self.core.output.size(),
+                0,
+                choices.len(),
+                self.current,
+                |i| ListEntry {
+                    marker: None,
+                    text: choices[i].to_string(),
+                },
+            );
         }
-        if self.state == PromptState::Success {
-            queue!(
-                stdout,
-                Print(" "),
-                print_input_icon(&self.state),
-                Print(self.choices[self.current].to_string())
-            )?;
+        if self.core.state == PromptState::Success {
+            frame.print(" ");
+            frame.print_styled(print_input_icon(&self.core.state).0);
+            frame.print(&self.choices[self.current]);
         }
-        if self.state.is_done() {
-            queue!(stdout, Print("\n\r"), cursor::Show)?;
+        if self.core.state.is_done() {
+            frame.new_line();
+            queue!(self.core.output, cursor::Show)?;
         }
-        stdout.flush()?;
-        crossterm::Result::Ok(())
+        self.core.render(&frame)
     }
     fn handle_key_event(&mut self, event: KeyEvent) {
         if is_abort_event(event) {
-            self.state = PromptState::Aborted;
+            self.core.state = PromptState::Aborted;
             return;
         }
         if event.modifiers == KeyModifiers::empty() {
             match event.code {
-                KeyCode::Enter => self.state = PromptState::Success,
+                KeyCode::Enter => self.core.state = PromptState::Success,
                 KeyCode::Home => {
                     self.current = 0;
                 }
@@ -198,7 +194,7 @@ impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<T> for
                     self.current = self.choices.len() - 1;
                 }
                 KeyCode::Char('k') | KeyCode::Up => {
-                    self.current = self.current.checked_sub(1).unwrap_or(0);
+                    self.current = self.current.saturating_sub(1);
                 }
                 KeyCode::Char('j') | KeyCode::Down => {
                     self.current = cmp::min(self.current + 1, self.choices.len() - 1);
@@ -207,4 +203,264 @@ impl<T: std::clone::Clone + std::marker::Send + std::fmt::Display> Prompt<T> for
             }
         }
     }
+    fn handle_mouse_event(&mut self, event: MouseEvent) {
+        match event {
+            MouseEvent::Down(MouseButton::Left, _, row, _) => {
+                let line = self.core.line_at(row);
+                if let Some(index) = line.and_then(|line| self.list.clicked_entry(line)) {
+                    self.current = index;
+                    if self.clicks.click(index) {
+                        self.core.state = PromptState::Success;
+                    }
+                }
+            }
+            MouseEvent::ScrollUp(..) => {
+                self.current = self.current.saturating_sub(1);
+            }
+            MouseEvent::ScrollDown(..) => {
+                self.current = cmp::min(self.current + 1, self.choices.len() - 1);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::VirtualTerminal, utils::key_events};
+    use crossterm::{event::Event, style::Color};
+    #[cfg(feature = "async")]
+    use futures::executor::block_on;
+    use std::io::sink;
+    use std::iter;
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run_with_arrow_keys() {
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["The", "quick", "brown", "fox"])
+            .with_output(Output::new(sink()));
+        let events = vec![
+            KeyEvent::from(KeyCode::Down),
+            KeyEvent::from(KeyCode::Char('j')),
+            KeyEvent::from(KeyCode::Up),
+            KeyEvent::from(KeyCode::Enter),
+        ];
+        let result = block_on(prompt.run_with_events(key_events(events)));
+        assert_eq!(result.unwrap(), "quick");
+    }
+
+    #[test]
+    fn run_aborted() {
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["The", "quick"])
+            .with_output(Output::new(sink()));
+        let events = vec![KeyEvent::from(KeyCode::Esc)];
+        let result = prompt.run_blocking_with_events(key_events(events));
+        match result {
+            Err(PromptError::Cancelled) => {}
+            _ => panic!("expected a Cancelled error"),
+        }
+    }
+
+    #[test]
+    fn display_pointer_follows_current() {
+        let terminal = VirtualTerminal::new(40, 10);
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["The", "quick", "brown", "fox"])
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+        prompt.handle_key_event(KeyEvent::from(KeyCode::Down));
+        prompt.display().unwrap();
+        prompt.handle_key_event(KeyEvent::from(KeyCode::Down));
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(
+            screen.text(),
+            "? Choose a word\n    The\n    quick\n❯   brown\n    fox"
+        );
+        assert_eq!(screen.cell(0, 3).style.foreground, Some(Color::Cyan));
+        assert!(screen.cell(4, 3).style.bold);
+        assert!(!screen.cursor_visible);
+    }
+
+    #[test]
+    fn display_scrolls_with_limit() {
+        let terminal = VirtualTerminal::new(40, 4);
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["a", "b", "c", "d", "e"])
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+        for _ in 0..3 {
+            prompt.handle_key_event(KeyEvent::from(KeyCode::Down));
+            prompt.display().unwrap();
+        }
+        assert_eq!(
+            terminal.screen().text(),
+            "? Choose a word\n  ↑ c\n❯   d\n    e"
+        );
+    }
+
+    #[test]
+    fn display_clears_wrapped_lines() {
+        let terminal = VirtualTerminal::new(12, 10);
+        let mut output = terminal.output();
+        write!(output, "before\n\r").unwrap();
+        let mut prompt =
+            SelectPrompt::new("Pick a long word", vec!["extraordinary", "b"]).with_output(output);
+        prompt.display().unwrap();
+        prompt.handle_key_event(KeyEvent::from(KeyCode::Down));
+        prompt.display().unwrap();
+        prompt.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        prompt.display().unwrap();
+
+        let frames = terminal.frames();
+        assert_eq!(
+            frames[1].text(),
+            "before\n? Pick a lon\ng word\n    extraord\ninary\n❯   b"
+        );
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "before\n✔ Pick a lon\ng word … b");
+        assert_eq!(screen.cursor, (0, 3));
+    }
+
+    #[test]
+    fn display_fits_wrapped_message_on_short_terminal() {
+        let terminal = VirtualTerminal::new(20, 4);
+        let mut prompt = SelectPrompt::new("A fairly long question here", vec!["a", "b", "c", "d"])
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+        for _ in 0..3 {
+            prompt.handle_key_event(KeyEvent::from(KeyCode::Down));
+            prompt.display().unwrap();
+        }
+
+        for frame in terminal.frames() {
+            assert_eq!(frame.row(0), "? A fairly long ques");
+            assert_eq!(frame.text().matches('❯').count(), 1);
+        }
+        assert_eq!(
+            terminal.screen().text(),
+            "? A fairly long ques\ntion here\n  ↑ c\n❯   d"
+        );
+    }
+
+    #[test]
+    fn display_answer_on_success() {
+        let terminal = VirtualTerminal::new(40, 10);
+        let mut prompt =
+            SelectPrompt::new("Choose a word", vec!["The", "quick"]).with_output(terminal.output());
+        prompt.display().unwrap();
+        prompt.handle_key_event(KeyEvent::from(KeyCode::Enter));
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "✔ Choose a word … The");
+        assert_eq!(screen.cursor, (0, 1));
+        assert!(screen.cursor_visible);
+    }
+
+    #[test]
+    fn display_relayouts_on_resize() {
+        let terminal = VirtualTerminal::new(40, 10);
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["a", "b", "c", "d", "e"])
+            .with_output(terminal.output());
+        let resized = terminal.clone();
+        let events =
+            key_events(vec![KeyEvent::from(KeyCode::Down); 2]).chain(iter::once_with(move || {
+                resized.resize(40, 4);
+                Ok(Event::Resize(40, 4))
+            }));
+        assert!(prompt.run_blocking_with_events(events).is_err());
+        assert_eq!(
+            terminal.screen().text(),
+            "? Choose a word\n  ↑ b\n❯   c\n  ↓ d"
+        );
+    }
+
+    fn click(row: u16) -> crossterm::Result<Event> {
+        Ok(Event::Mouse(MouseEvent::Down(
+            MouseButton::Left,
+            4,
+            row,
+            KeyModifiers::empty(),
+        )))
+    }
+
+    #[test]
+    fn run_with_mouse() {
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["The", "quick", "brown", "fox"])
+            .with_output(Output::new(sink()))
+            .with_mouse();
+        let scroll = Ok(Event::Mouse(MouseEvent::ScrollDown(
+            4,
+            1,
+            KeyModifiers::empty(),
+        )));
+        let events = vec![scroll, click(4), click(3), click(3)];
+        let result = prompt.run_blocking_with_events(events.into_iter());
+        assert_eq!(result.unwrap(), "brown");
+    }
+
+    #[test]
+    fn run_with_mouse_below_multi_line_message() {
+        let mut prompt = SelectPrompt::new("Intro\nPick one", vec!["a", "b", "c"])
+            .with_output(Output::new(sink()))
+            .with_mouse();
+        let result = prompt.run_blocking_with_events(vec![click(2), click(2)].into_iter());
+        assert_eq!(result.unwrap(), "a");
+    }
+
+    #[test]
+    fn run_ignores_mouse_by_default() {
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["The", "quick", "brown", "fox"])
+            .with_output(Output::new(sink()));
+        let events = vec![click(3), click(3), Ok(Event::Key(KeyCode::Enter.into()))];
+        let result = prompt.run_blocking_with_events(events.into_iter());
+        assert_eq!(result.unwrap(), "The");
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run_times_out() {
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["The", "quick"])
+            .with_timeout(Duration::from_millis(10))
+            .with_output(Output::new(sink()));
+        match block_on(prompt.run_with_events(futures::stream::pending())) {
+            Err(PromptError::Timeout) => {}
+            _ => panic!("expected a Timeout error"),
+        }
+    }
+
+    #[test]
+    fn run_blocking_notices_timeout_on_next_event() {
+        let mut prompt = SelectPrompt::new("Choose a word", vec!["The", "quick"])
+            .with_timeout(Duration::from_secs(0))
+            .with_output(Output::new(sink()));
+        let events = vec![KeyEvent::from(KeyCode::Enter)];
+        match prompt.run_blocking_with_events(key_events(events)) {
+            Err(PromptError::Timeout) => {}
+            _ => panic!("expected a Timeout error"),
+        }
+    }
+
+    #[test]
+    fn run_without_choices() {
+        let mut prompt =
+            SelectPrompt::<&str>::new("Choose a word", vec![]).with_output(Output::new(sink()));
+        let result = prompt.run_blocking_with_events(key_events(Vec::<KeyEvent>::new()));
+        match result {
+            Err(PromptError::InvalidConfiguration(_)) => {}
+            _ => panic!("expected an InvalidConfiguration error"),
+        }
+    }
+
+    #[test]
+    fn run_lines_by_text_or_index() {
+        let choices = vec!["The", "quick", "brown"];
+        let mut prompt =
+            SelectPrompt::new("Choose a word", choices.clone()).with_output(Output::new(sink()));
+        assert_eq!(prompt.run_lines("brown\n".as_bytes()).unwrap(), "brown");
+        let mut prompt =
+            SelectPrompt::new("Choose a word", choices.clone()).with_output(Output::new(sink()));
+        assert_eq!(prompt.run_lines("fox\n1\n".as_bytes()).unwrap(), "quick");
+    }
 }
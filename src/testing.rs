@@ -0,0 +1,569 @@
+//! In-memory virtual terminal for testing how prompts render
+//!
+//! `VirtualTerminal` applies the escape codes crossterm emits (cursor movement,
+//! clearing and styling) to a grid of cells, and records a `Snapshot` of the
+//! screen every time the output is flushed, which prompts do once per `display()`.
+//!
+//! # Examples
+//!
+//! ```
+//! use prompts::{select::SelectPrompt, testing::VirtualTerminal, Prompt};
+//! use crossterm::event::{KeyCode, KeyEvent};
+//!
+//! let terminal = VirtualTerminal::new(80, 24);
+//! let mut prompt =
+//!     SelectPrompt::new("Choose a word", vec!["The", "quick", "brown"]).with_output(terminal.output());
+//!
+//! prompt.display().unwrap();
+//! prompt.handle_key_event(KeyEvent::from(KeyCode::Down));
+//! prompt.display().unwrap();
+//!
+//! assert!(terminal.screen().row(2).starts_with("❯"));
+//! ```
+
+use crate::utils::Output;
+use crossterm::style::Color;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use unicode_width::UnicodeWidthChar;
+
+/// Style of a single cell
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CellStyle {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub dim: bool,
+    pub italic: bool,
+    pub underlined: bool,
+    pub reverse: bool,
+}
+
+/// A single cell on the screen and its style
+///
+/// Holds one character, plus any zero-width characters combined with it.
+/// Wide characters take up two cells, leaving the second one empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cell {
+    pub content: String,
+    pub style: CellStyle,
+}
+impl Default for Cell {
+    fn default() -> Cell {
+        Cell {
+            content: " ".to_string(),
+            style: CellStyle::default(),
+        }
+    }
+}
+
+/// Snapshot of the whole screen
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Cells, indexed by row and then column
+    pub cells: Vec<Vec<Cell>>,
+    /// Cursor position as (column, row)
+    pub cursor: (u16, u16),
+    pub cursor_visible: bool,
+}
+impl Snapshot {
+    /// Returns the text of a row, without trailing whitespace
+    pub fn row(&self, row: usize) -> String {
+        self.cells[row]
+            .iter()
+            .map(|cell| cell.content.as_str())
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Returns the text of the whole screen, without trailing whitespace
+    pub fn text(&self) -> String {
+        (0..self.cells.len())
+            .map(|row| self.row(row))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_end()
+            .to_string()
+    }
+
+    /// Returns the cell at the given position
+    pub fn cell(&self, column: usize, row: usize) -> &Cell {
+        &self.cells[row][column]
+    }
+}
+
+#[derive(Debug)]
+enum ParserState {
+    Ground,
+    Escape,
+    Csi(String),
+}
+
+#[derive(Debug)]
+struct Screen {
+    columns: u16,
+    rows: u16,
+    cells: Vec<Vec<Cell>>,
+    column: u16,
+    row: u16,
+    wrap_pending: bool,
+    saved: (u16, u16),
+    style: CellStyle,
+    cursor_visible: bool,
+    parser: ParserState,
+    pending: Vec<u8>,
+    frames: Vec<Snapshot>,
+}
+impl Screen {
+    fn new(columns: u16, rows: u16) -> Screen {
+        Screen {
+            columns,
+            rows,
+            cells: vec![vec![Cell::default(); columns as usize]; rows as usize],
+            column: 0,
+            row: 0,
+            wrap_pending: false,
+            saved: (0, 0),
+            style: CellStyle::default(),
+            cursor_visible: true,
+            parser: ParserState::Ground,
+            pending: vec![],
+            frames: vec![],
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            cells: self.cells.clone(),
+            cursor: (self.column, self.row),
+            cursor_visible: self.cursor_visible,
+        }
+    }
+
+    fn write(&mut self, buf: &[u8]) {
+        self.pending.extend_from_slice(buf);
+        let valid = match std::str::from_utf8(&self.pending) {
+            Ok(s) => s.len(),
+            Err(e) => e.valid_up_to(),
+        };
+        let input = String::from_utf8(self.pending.drain(..valid).collect()).unwrap();
+        for c in input.chars() {
+            self.feed(c);
+        }
+    }
+
+    fn feed(&mut self, c: char) {
+        match std::mem::replace(&mut self.parser, ParserState::Ground) {
+            ParserState::Ground => match c {
+                '\x1B' => self.parser = ParserState::Escape,
+                '\n' => self.line_feed(),
+                '\r' => self.move_to_column(0),
+                c if c.is_control() => {}
+                c => self.print(c),
+            },
+            ParserState::Escape => match c {
+                '[' => self.parser = ParserState::Csi(String::new()),
+                '7' => self.saved = (self.column, self.row),
+                '8' => {
+                    let (column, row) = self.saved;
+                    self.move_to(column, row);
+                }
+                _ => {}
+            },
+            ParserState::Csi(mut params) => {
+                if ('\x40'..='\x7E').contains(&c) {
+                    self.csi(&params, c);
+                } else {
+                    params.push(c);
+                    self.parser = ParserState::Csi(params);
+                }
+            }
+        }
+    }
+
+    fn csi(&mut self, params: &str, command: char) {
+        if params.starts_with('?') {
+            match (params, command) {
+                ("?25", 'l') => self.cursor_visible = false,
+                ("?25", 'h') => self.cursor_visible = true,
+                _ => {}
+            }
+            return;
+        }
+        let args = params
+            .split(';')
+            .map(|arg| arg.parse::<u16>().unwrap_or(0))
+            .collect::<Vec<_>>();
+        // Cursor movements treat a missing or zero count as one, like real terminals
+        let count = match args.first() {
+            Some(0) | None => 1,
+            Some(n) => *n,
+        };
+        match command {
+            'A' => self.move_to(self.column, self.row.saturating_sub(count)),
+            'B' => self.move_to(self.column, self.row.saturating_add(count)),
+            'C' => self.move_to(self.column.saturating_add(count), self.row),
+            'D' => self.move_to(self.column.saturating_sub(count), self.row),
+            'E' => self.move_to(0, self.row.saturating_add(count)),
+            'F' => self.move_to(0, self.row.saturating_sub(count)),
+            'G' => self.move_to_column(count - 1),
+            'H' => {
+                let column = match args.get(1) {
+                    Some(0) | None => 1,
+                    Some(n) => *n,
+                };
+                self.move_to(column - 1, count - 1);
+            }
+            'J' => self.clear_screen(args.first().cloned().unwrap_or(0)),
+            'K' => self.clear_line(args.first().cloned().unwrap_or(0)),
+            'm' => self.set_style(&args),
+            _ => {}
+        }
+    }
+
+    fn resize(&mut self, columns: u16, rows: u16) {
+        for row in self.cells.iter_mut() {
+            row.resize(columns as usize, Cell::default());
+        }
+        self.cells
+            .resize(rows as usize, vec![Cell::default(); columns as usize]);
+        self.columns = columns;
+        self.rows = rows;
+        self.move_to(self.column, self.row);
+    }
+
+    fn move_to(&mut self, column: u16, row: u16) {
+        self.column = column.min(self.columns - 1);
+        self.row = row.min(self.rows - 1);
+        self.wrap_pending = false;
+    }
+
+    fn move_to_column(&mut self, column: u16) {
+        self.move_to(column, self.row);
+    }
+
+    fn line_feed(&mut self) {
+        self.wrap_pending = false;
+        if self.row + 1 == self.rows {
+            self.cells.remove(0);
+            self.cells
+                .push(vec![Cell::default(); self.columns as usize]);
+        } else {
+            self.row += 1;
+        }
+    }
+
+    fn print(&mut self, c: char) {
+        let width = c.width().unwrap_or(0) as u16;
+        if width == 0 {
+            self.join_previous(c);
+            return;
+        }
+        if self.wrap_pending || self.column + width > self.columns {
+            self.line_feed();
+            self.column = 0;
+        }
+        let row = &mut self.cells[self.row as usize];
+        row[self.column as usize] = Cell {
+            content: c.to_string(),
+            style: self.style.clone(),
+        };
+        if width == 2 {
+            row[self.column as usize + 1] = Cell {
+                content: String::new(),
+                style: self.style.clone(),
+            };
+        }
+        if self.column + width == self.columns {
+            self.column = self.columns - 1;
+            self.wrap_pending = true;
+        } else {
+            self.column += width;
+        }
+    }
+
+    /// Adds a zero-width character, like a combining accent, to the character before the cursor
+    fn join_previous(&mut self, c: char) {
+        let row = &mut self.cells[self.row as usize];
+        let mut column = self.column as usize;
+        if !self.wrap_pending {
+            if column == 0 {
+                return;
+            }
+            column -= 1;
+        }
+        // The second half of a wide character belongs to the cell before it
+        if row[column].content.is_empty() && column > 0 {
+            column -= 1;
+        }
+        row[column].content.push(c);
+    }
+
+    fn clear_cells(&mut self, row: u16, columns: std::ops::Range<u16>) {
+        for column in columns {
+            self.cells[row as usize][column as usize] = Cell::default();
+        }
+    }
+
+    fn clear_line(&mut self, mode: u16) {
+        match mode {
+            0 => self.clear_cells(self.row, self.column..self.columns),
+            1 => self.clear_cells(self.row, 0..self.column + 1),
+            _ => self.clear_cells(self.row, 0..self.columns),
+        }
+    }
+
+    fn clear_screen(&mut self, mode: u16) {
+        match mode {
+            0 => {
+                self.clear_line(0);
+                for row in self.row + 1..self.rows {
+                    self.clear_cells(row, 0..self.columns);
+                }
+            }
+            1 => {
+                self.clear_line(1);
+                for row in 0..self.row {
+                    self.clear_cells(row, 0..self.columns);
+                }
+            }
+            _ => {
+                for row in 0..self.rows {
+                    self.clear_cells(row, 0..self.columns);
+                }
+            }
+        }
+    }
+
+    fn set_style(&mut self, args: &[u16]) {
+        let mut args = args.iter().cloned();
+        while let Some(arg) = args.next() {
+            match arg {
+                0 => self.style = CellStyle::default(),
+                1 => self.style.bold = true,
+                2 => self.style.dim = true,
+                3 => self.style.italic = true,
+                4 => self.style.underlined = true,
+                7 => self.style.reverse = true,
+                21 | 22 => {
+                    self.style.bold = false;
+                    self.style.dim = false;
+                }
+                23 => self.style.italic = false,
+                24 => self.style.underlined = false,
+                27 => self.style.reverse = false,
+                30..=37 => self.style.foreground = Some(ansi_color(arg - 30)),
+                90..=97 => self.style.foreground = Some(ansi_color(arg - 90 + 8)),
+                40..=47 => self.style.background = Some(ansi_color(arg - 40)),
+                100..=107 => self.style.background = Some(ansi_color(arg - 100 + 8)),
+                38 => self.style.foreground = extended_color(&mut args),
+                48 => self.style.background = extended_color(&mut args),
+                39 => self.style.foreground = None,
+                49 => self.style.background = None,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Maps an ANSI color index to the crossterm color that produces it
+fn ansi_color(value: u16) -> Color {
+    match value {
+        0 => Color::Black,
+        1 => Color::DarkRed,
+        2 => Color::DarkGreen,
+        3 => Color::DarkYellow,
+        4 => Color::DarkBlue,
+        5 => Color::DarkMagenta,
+        6 => Color::DarkCyan,
+        7 => Color::Grey,
+        8 => Color::DarkGrey,
+        9 => Color::Red,
+        10 => Color::Green,
+        11 => Color::Yellow,
+        12 => Color::Blue,
+        13 => Color::Magenta,
+        14 => Color::Cyan,
+        15 => Color::White,
+        n => Color::AnsiValue(n as u8),
+    }
+}
+
+/// Parses the rest of a `38;5;n` or `38;2;r;g;b` color
+fn extended_color(args: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    match args.next() {
+        Some(5) => args.next().map(ansi_color),
+        Some(2) => {
+            let mut rgb = args.take(3).map(|value| value as u8);
+            Some(Color::Rgb {
+                r: rgb.next()?,
+                g: rgb.next()?,
+                b: rgb.next()?,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// In-memory terminal that prompts can render to
+///
+/// Clones share the same screen, so keep one around to inspect what
+/// a prompt rendered into the `Output` returned by `output`.
+#[derive(Clone, Debug)]
+pub struct VirtualTerminal {
+    screen: Arc<Mutex<Screen>>,
+}
+impl VirtualTerminal {
+    /// Returns an empty VirtualTerminal
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Width of the screen
+    /// * `rows` - Height of the screen
+    pub fn new(columns: u16, rows: u16) -> VirtualTerminal {
+        VirtualTerminal {
+            screen: Arc::new(Mutex::new(Screen::new(columns, rows))),
+        }
+    }
+
+    /// Returns an Output that renders to this terminal, with a matching size
+    pub fn output(&self) -> Output {
+        let (columns, rows) = {
+            let screen = self.screen.lock().unwrap();
+            (screen.columns, screen.rows)
+        };
+        Output::new(self.clone()).with_size(columns, rows)
+    }
+
+    /// Changes the size of the screen, like resizing a terminal window
+    ///
+    /// Content is cut off or padded, not reflowed. Send an `Event::Resize`
+    /// to let a running prompt know.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - New width of the screen
+    /// * `rows` - New height of the screen
+    pub fn resize(&self, columns: u16, rows: u16) {
+        self.screen.lock().unwrap().resize(columns, rows);
+    }
+
+    /// Returns the current state of the screen
+    pub fn screen(&self) -> Snapshot {
+        self.screen.lock().unwrap().snapshot()
+    }
+
+    /// Returns a snapshot of every frame recorded so far, one per flush
+    pub fn frames(&self) -> Vec<Snapshot> {
+        self.screen.lock().unwrap().frames.clone()
+    }
+}
+impl Write for VirtualTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.screen.lock().unwrap().write(buf);
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        let mut screen = self.screen.lock().unwrap();
+        let frame = screen.snapshot();
+        screen.frames.push(frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::{
+        cursor, queue,
+        style::{style, Attribute, Print, PrintStyledContent},
+        terminal::{Clear, ClearType},
+    };
+
+    #[test]
+    fn print_and_move() {
+        let mut terminal = VirtualTerminal::new(10, 3);
+        queue!(
+            terminal,
+            Print("abc\n\rdef"),
+            cursor::MoveUp(1),
+            cursor::MoveToColumn(2),
+            Print("X")
+        )
+        .unwrap();
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "aXc\ndef");
+        assert_eq!(screen.cursor, (2, 0));
+    }
+
+    #[test]
+    fn clear_from_cursor_down() {
+        let mut terminal = VirtualTerminal::new(10, 3);
+        queue!(
+            terminal,
+            Print("abc\n\rdef\n\rghi"),
+            cursor::MoveUp(1),
+            cursor::MoveToColumn(2),
+            Clear(ClearType::FromCursorDown)
+        )
+        .unwrap();
+        assert_eq!(terminal.screen().text(), "abc\nd");
+    }
+
+    #[test]
+    fn wrap_and_scroll() {
+        let mut terminal = VirtualTerminal::new(4, 2);
+        queue!(terminal, Print("abcdefgh\n\rij")).unwrap();
+        assert_eq!(terminal.screen().text(), "efgh\nij");
+    }
+
+    #[test]
+    fn resize_cuts_off_content() {
+        let mut terminal = VirtualTerminal::new(10, 3);
+        write!(terminal, "abcdefgh\n\rij").unwrap();
+        terminal.resize(4, 1);
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "abcd");
+        assert_eq!(screen.cursor, (2, 0));
+    }
+
+    #[test]
+    fn wide_and_combining_characters() {
+        let mut terminal = VirtualTerminal::new(4, 3);
+        write!(terminal, "名e\u{301}前x").unwrap();
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "名e\u{301}\n前x");
+        assert_eq!(screen.cell(0, 0).content, "名");
+        assert_eq!(screen.cell(1, 0).content, "");
+        assert_eq!(screen.cursor, (3, 1));
+    }
+
+    #[test]
+    fn styles() {
+        let mut terminal = VirtualTerminal::new(10, 1);
+        queue!(
+            terminal,
+            PrintStyledContent(style("a").with(Color::Cyan).attribute(Attribute::Bold)),
+            Print("b")
+        )
+        .unwrap();
+        let screen = terminal.screen();
+        assert_eq!(screen.cell(0, 0).style.foreground, Some(Color::Cyan));
+        assert!(screen.cell(0, 0).style.bold);
+        assert_eq!(screen.cell(1, 0).style, CellStyle::default());
+    }
+
+    #[test]
+    fn frame_per_flush() {
+        let mut terminal = VirtualTerminal::new(10, 1);
+        queue!(terminal, Print("a")).unwrap();
+        terminal.flush().unwrap();
+        queue!(terminal, Print("b")).unwrap();
+        terminal.flush().unwrap();
+        let frames = terminal.frames();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].text(), "a");
+        assert_eq!(frames[1].text(), "ab");
+    }
+}
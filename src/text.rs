@@ -1,24 +1,27 @@
-//! Interactive prompt that accepts text input
+//! Interactive prompts that accept text input
 
+#[cfg(feature = "async")]
+use crate::utils::Validation;
 use crate::{
-    utils::{is_abort_event, print_input_icon, print_state_icon, Figures, PromptState},
+    error::PromptError,
+    utils::{
+        is_abort_event, print_input_icon, print_state_icon, Figures, Frame, Output, PromptCore,
+        PromptState, TextInput,
+    },
     Prompt,
 };
-use async_trait::async_trait;
 use crossterm::{
-    cursor,
-    event::{Event, EventStream, KeyCode, KeyEvent, KeyModifiers},
-    queue,
-    style::{style, Attribute, Color, Print, PrintStyledContent},
-    terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    style::{style, Attribute, Color},
 };
-use futures::StreamExt;
-use std::cmp;
 use std::fmt;
-use std::io::{stdout, Write};
+use std::str::FromStr;
+use std::time::Duration;
+use unicode_segmentation::UnicodeSegmentation;
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub enum Style {
+    #[default]
     Normal,
     Password,
     Invisible,
@@ -26,59 +29,76 @@ pub enum Style {
 impl Style {
     fn transform(&self, input: &str) -> String {
         match self {
-            Style::Normal => return String::from(input),
-            Style::Password => return (0..input.len()).map(|_| "*").collect::<String>(),
-            Style::Invisible => return String::default(),
+            Style::Normal => String::from(input),
+            Style::Password => "*".repeat(input.graphemes(true).count()),
+            Style::Invisible => String::default(),
         }
     }
-    fn cursor_mult(&self) -> usize {
-        match self {
-            Style::Normal => return 1,
-            Style::Password => return 1,
-            Style::Invisible => return 0,
-        }
-    }
-}
-impl Default for Style {
-    fn default() -> Style {
-        Style::Normal
-    }
 }
 
+/// Validation function that returns Ok if the input is valid,
+/// or an error message to show if it is not
+type Validator = Box<dyn Fn(&str) -> std::result::Result<(), String> + Send>;
+
+/// Validation function that checks the input in the background
+#[cfg(feature = "async")]
+type AsyncValidator = Box<dyn Fn(String) -> Validation + Send>;
+
 /// Interactive prompt that accepts text input
 ///
 /// # Examples
 ///
 /// ```
-/// use prompt::{text::TextPrompt, Prompt};
+/// use prompts::{error::PromptError, text::TextPrompt, Prompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
 /// let mut prompt = TextPrompt::new("What is your name?");
 /// match prompt.run().await {
-///     Ok(Some(s)) => println!("You wrote: {}", s),
-///     Ok(None) => println!("Prompt was aborted!"),
-///     Err(e) => println!("Some kind of crossterm error happened: {:?}", e),
+///     Ok(s) => println!("You wrote: {}", s),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
 /// }
+/// # }
 /// ```
 
 #[derive(Default)]
 pub struct TextPrompt {
     message: String,
-    state: PromptState,
-    input: String,
-    cursor: usize,
+    core: PromptCore,
+    input: TextInput,
     style: Style,
-    validator: Option<fn(input: &str) -> std::result::Result<(), String>>,
-    error: Option<String>,
+    validator: Option<Validator>,
+    #[cfg(feature = "async")]
+    async_validator: Option<AsyncValidator>,
 }
 impl fmt::Debug for TextPrompt {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.debug_struct("TextPrompt")
-           .field("message", &self.message)
-           .field("style", &self.style)
-           .field("validator", &format_args!("{}", &match self.validator {
+        let mut debug = fmt.debug_struct("TextPrompt");
+        debug
+            .field("message", &self.message)
+            .field("style", &self.style)
+            .field(
+                "validator",
+                &format_args!(
+                    "{}",
+                    &match self.validator {
+                        Some(_) => "custom validator",
+                        None => "None",
+                    }
+                ),
+            );
+        #[cfg(feature = "async")]
+        debug.field(
+            "async_validator",
+            &format_args!(
+                "{}",
+                &match self.async_validator {
                     Some(_) => "custom validator",
                     None => "None",
-                }))
-           .finish()
+                }
+            ),
+        );
+        debug.finish()
     }
 }
 impl TextPrompt {
@@ -112,127 +132,876 @@ impl TextPrompt {
     /// # Arguments
     ///
     /// * `validator` - Validation closure that accepts a string, and returns
-    ///     Ok if valid, or a string error to show if invalid.
-    pub fn with_validator(
-        mut self,
-        validator: fn(input: &str) -> std::result::Result<(), String>,
-    ) -> TextPrompt {
-        self.validator = Some(validator);
+    ///   Ok if valid, or a string error to show if invalid.
+    pub fn with_validator<F>(mut self, validator: F) -> TextPrompt
+    where
+        F: Fn(&str) -> std::result::Result<(), String> + Send + 'static,
+    {
+        self.validator = Some(Box::new(validator));
         self
     }
-}
-#[async_trait]
-impl Prompt<String> for TextPrompt {
-    async fn run(&mut self) -> std::result::Result<Option<String>, crossterm::ErrorKind> {
-        enable_raw_mode()?;
-        let mut reader = EventStream::new();
-
-        self.display()?;
-
-        loop {
-            match reader.next().await {
-                Some(Ok(Event::Key(event))) => self.handle_key_event(event),
-                Some(Err(e)) => {
-                    disable_raw_mode()?;
-                    return Err(e);
-                }
-                _ => {}
-            }
 
-            match self.state {
-                PromptState::Validate => match self.validator {
-                    Some(validator) => match validator(&self.input) {
-                        Ok(()) => self.state = PromptState::Success,
-                        Err(msg) => {
-                            self.error = Some(msg);
-                            self.state = PromptState::Running;
-                        }
-                    },
-                    None => self.state = PromptState::Success,
-                },
-                _ => (),
-            }
+    /// Provide a custom validation closure that runs in the background
+    ///
+    /// It runs after the validator set with `with_validator` accepted the input.
+    /// Until it completes, the prompt shows that it is validating and ignores
+    /// key presses other than those that abort it.
+    ///
+    /// # Arguments
+    ///
+    /// * `validator` - Validation closure that accepts the input, and returns
+    ///   a `Validation` future that resolves to Ok if valid, or a string error to show if invalid.
+    #[cfg(feature = "async")]
+    pub fn with_async_validator<F>(mut self, validator: F) -> TextPrompt
+    where
+        F: Fn(String) -> Validation + Send + 'static,
+    {
+        self.async_validator = Some(Box::new(validator));
+        self
+    }
 
-            self.display()?;
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> TextPrompt {
+        self.core.output = output;
+        self
+    }
 
-            match self.state {
-                PromptState::Aborted => {
-                    disable_raw_mode()?;
-                    return Ok(None);
-                }
-                PromptState::Success => {
-                    disable_raw_mode()?;
-                    return Ok(Some(self.input.clone()));
-                }
-                _ => (),
-            }
+    /// Give up waiting for an answer after some time
+    ///
+    /// Running the prompt then fails with `PromptError::Timeout`,
+    /// even if something was typed already.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> TextPrompt {
+        self.core.timeout = Some(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> TextPrompt {
+        self.core.countdown = true;
+        self
+    }
+}
+impl Prompt<String> for TextPrompt {
+    fn line_question(&self) -> Option<String> {
+        Some(self.message.clone())
+    }
+    /// Accepts any line that passes the validator
+    fn parse_line(&mut self, line: &str) -> std::result::Result<String, String> {
+        if let Some(validator) = &self.validator {
+            validator(line)?;
+        }
+        self.input = TextInput::new(line);
+        Ok(self.result())
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        match &self.validator {
+            Some(validator) => validator(self.input.as_str()),
+            None => Ok(()),
         }
     }
+    #[cfg(feature = "async")]
+    fn validate_async(&mut self) -> Option<Validation> {
+        let validator = self.async_validator.as_ref()?;
+        Some(validator(self.input.as_str().to_string()))
+    }
+    fn result(&self) -> String {
+        self.input.as_str().to_string()
+    }
     fn display(&mut self) -> crossterm::Result<()> {
-        let mut stdout = stdout();
-        queue!(
-            stdout,
-            cursor::MoveToColumn(0),
-            Clear(ClearType::FromCursorDown),
-            print_state_icon(&self.state),
-            Print(" "),
-            PrintStyledContent(style(&self.message).attribute(Attribute::Bold)),
-            Print(" "),
-            print_input_icon(&self.state),
-        )?;
-        if !self.state.is_done() {
-            let input_column =
-                (2 + self.message.len() + 3 + (self.cursor * self.style.cursor_mult()) + 1) as u16;
-
-            queue!(stdout, Print(self.style.transform(&self.input)),)?;
-            match &self.error {
-                None => queue!(stdout, cursor::MoveToColumn(input_column))?,
-                Some(msg) => queue!(
-                    stdout,
-                    Print(format!("\n\r{} ", Figures::PointerSmall.as_str())),
-                    PrintStyledContent(style(msg).with(Color::Red).attribute(Attribute::Italic)),
-                    cursor::MoveToPreviousLine(1),
-                    cursor::MoveToColumn(input_column)
-                )?,
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        frame.print(" ");
+        frame.print_styled(print_input_icon(&self.core.state).0);
+        if !self.core.state.is_done() {
+            frame.print(self.style.transform(self.input.before_cursor()));
+            frame.set_cursor();
+            frame.print(self.style.transform(self.input.after_cursor()));
+            if self.core.state == PromptState::Validating {
+                frame.new_line();
+                frame.print(format!("{} ", Figures::PointerSmall.as_str()));
+                frame.print_styled(
+                    style(format!("validating{}", Figures::Ellipsis.as_str()))
+                        .with(Color::DarkGrey)
+                        .attribute(Attribute::Italic),
+                );
+            } else if let Some(msg) = &self.core.error {
+                frame.new_line();
+                frame.print(format!("{} ", Figures::PointerSmall.as_str()));
+                frame.print_styled(style(msg).with(Color::Red).attribute(Attribute::Italic));
             }
         } else {
-            queue!(stdout, Print("\n\r"))?;
+            frame.new_line();
         }
-        stdout.flush()?;
-        crossterm::Result::Ok(())
+        self.core.render(&frame)
     }
     fn handle_key_event(&mut self, event: KeyEvent) {
         if is_abort_event(event) {
-            self.state = PromptState::Aborted;
+            self.core.state = PromptState::Aborted;
             return;
         }
         if event.modifiers == KeyModifiers::empty() {
             match event.code {
-                KeyCode::Enter => self.state = PromptState::Validate,
-                KeyCode::Backspace => {
-                    self.cursor = self.cursor.checked_sub(1).unwrap_or(0);
-                    if self.input.len() > self.cursor {
-                        self.input.remove(self.cursor);
-                    }
-                }
-                KeyCode::Left => {
-                    self.cursor = self.cursor.checked_sub(1).unwrap_or(0);
-                }
-                KeyCode::Right => {
-                    self.cursor = cmp::min(self.cursor + 1, self.input.len());
-                }
-                KeyCode::Home => {
-                    self.cursor = 0;
-                }
-                KeyCode::End => {
-                    self.cursor = self.input.len();
-                }
-                KeyCode::Char(c) => {
-                    self.input.insert(self.cursor, c);
-                    self.cursor += 1;
-                }
+                KeyCode::Enter => self.core.state = PromptState::Validate,
+                KeyCode::Backspace => self.input.backspace(),
+                KeyCode::Delete => self.input.delete(),
+                KeyCode::Left => self.input.move_left(),
+                KeyCode::Right => self.input.move_right(),
+                KeyCode::Home => self.input.move_home(),
+                KeyCode::End => self.input.move_end(),
+                KeyCode::Char(c) => self.input.insert(c),
                 _ => {}
             }
         }
     }
 }
+
+/// Validation function for parsed input, like `Validator` but for the parsed value
+type ParsedValidator<T> = Box<dyn Fn(&T) -> std::result::Result<(), String> + Send>;
+
+/// Interactive prompt that parses text input into any type implementing `FromStr`
+///
+/// Keeps running until the input parses and passes the validator, showing
+/// why parsing failed below the input the same way validation errors are.
+///
+/// # Examples
+///
+/// ```
+/// use prompts::{error::PromptError, text::ParsePrompt, Prompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
+/// let mut prompt = ParsePrompt::<u16>::new("Which port?")
+///     .with_validator(|port| match *port >= 1024 {
+///         true => Ok(()),
+///         false => Err("Ports below 1024 are reserved".to_string()),
+///     });
+/// match prompt.run().await {
+///     Ok(port) => println!("Listening on port {}", port),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
+/// }
+/// # }
+/// ```
+pub struct ParsePrompt<T> {
+    text: TextPrompt,
+    validator: Option<ParsedValidator<T>>,
+    value: Option<T>,
+}
+
+impl<T> fmt::Debug for ParsePrompt<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ParsePrompt")
+            .field("message", &self.text.message)
+            .field(
+                "validator",
+                &format_args!(
+                    "{}",
+                    &match self.validator {
+                        Some(_) => "custom validator",
+                        None => "None",
+                    }
+                ),
+            )
+            .finish()
+    }
+}
+impl<T> ParsePrompt<T>
+where
+    T: FromStr + Clone + Send,
+    T::Err: fmt::Display,
+{
+    /// Returns a ParsePrompt ready to be run
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to display to the user before the prompt
+    pub fn new<S>(message: S) -> ParsePrompt<T>
+    where
+        S: Into<String>,
+    {
+        ParsePrompt {
+            text: TextPrompt::new(message),
+            validator: None,
+            value: None,
+        }
+    }
+
+    /// Provide a custom validation closure, which gets the parsed input
+    ///
+    /// # Arguments
+    ///
+    /// * `validator` - Validation closure that accepts the parsed input, and returns
+    ///   Ok if valid, or a string error to show if invalid.
+    pub fn with_validator<F>(mut self, validator: F) -> ParsePrompt<T>
+    where
+        F: Fn(&T) -> std::result::Result<(), String> + Send + 'static,
+    {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> ParsePrompt<T> {
+        self.text = self.text.with_output(output);
+        self
+    }
+
+    /// Give up waiting for an answer after some time
+    ///
+    /// Running the prompt then fails with `PromptError::Timeout`,
+    /// whether or not the input typed so far parses.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> ParsePrompt<T> {
+        self.text = self.text.with_timeout(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> ParsePrompt<T> {
+        self.text = self.text.with_countdown();
+        self
+    }
+}
+
+/// Parses the input and validates the result
+fn parse<T>(input: &str, validator: &Option<ParsedValidator<T>>) -> std::result::Result<T, String>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    let value = input.parse::<T>().map_err(|e| e.to_string())?;
+    if let Some(validator) = validator {
+        validator(&value)?;
+    }
+    Ok(value)
+}
+impl<T> Prompt<T> for ParsePrompt<T>
+where
+    T: FromStr + Clone + Send,
+    T::Err: fmt::Display,
+{
+    fn line_question(&self) -> Option<String> {
+        self.text.line_question()
+    }
+    /// Accepts any line that parses and passes the validator
+    fn parse_line(&mut self, line: &str) -> std::result::Result<T, String> {
+        let value = parse(line, &self.validator)?;
+        self.text.input = TextInput::new(line);
+        self.value = Some(value);
+        Ok(self.result())
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        self.text.core()
+    }
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        self.value = Some(parse(self.text.input.as_str(), &self.validator)?);
+        Ok(())
+    }
+    fn result(&self) -> T {
+        self.value
+            .clone()
+            .expect("the input was parsed before the prompt succeeded")
+    }
+    fn display(&mut self) -> crossterm::Result<()> {
+        self.text.display()
+    }
+    fn handle_key_event(&mut self, event: KeyEvent) {
+        self.text.handle_key_event(event)
+    }
+}
+
+/// Interactive prompt that splits text input into a list of entries
+///
+/// Splits the input on a separator, "," unless set with `with_separator`, and trims
+/// each entry. Keeps running until every entry passes the validator, showing
+/// which entry failed below the input.
+///
+/// # Examples
+///
+/// ```
+/// use prompts::{error::PromptError, text::ListPrompt, Prompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
+/// let mut prompt = ListPrompt::new("Which tags?").without_empty();
+/// match prompt.run().await {
+///     Ok(tags) => println!("Tagging with {}", tags.join(" and ")),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
+/// }
+/// # }
+/// ```
+pub struct ListPrompt {
+    text: TextPrompt,
+    separator: String,
+    skip_empty: bool,
+    validator: Option<Validator>,
+    value: Vec<String>,
+}
+
+impl fmt::Debug for ListPrompt {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("ListPrompt")
+            .field("message", &self.text.message)
+            .field("separator", &self.separator)
+            .field("skip_empty", &self.skip_empty)
+            .field(
+                "validator",
+                &format_args!(
+                    "{}",
+                    &match self.validator {
+                        Some(_) => "custom validator",
+                        None => "None",
+                    }
+                ),
+            )
+            .finish()
+    }
+}
+impl ListPrompt {
+    /// Returns a ListPrompt ready to be run
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to display to the user before the prompt
+    pub fn new<S>(message: S) -> ListPrompt
+    where
+        S: Into<String>,
+    {
+        ListPrompt {
+            text: TextPrompt::new(message),
+            separator: ",".to_string(),
+            skip_empty: false,
+            validator: None,
+            value: Vec::new(),
+        }
+    }
+
+    /// Set what separates the entries, "," by default
+    pub fn with_separator<S>(mut self, separator: S) -> ListPrompt
+    where
+        S: Into<String>,
+    {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Leave out entries that are empty after trimming, e.g. in "a,,b" or "a, b,"
+    pub fn without_empty(mut self) -> ListPrompt {
+        self.skip_empty = true;
+        self
+    }
+
+    /// Provide a custom validation closure, which is called for each entry
+    ///
+    /// # Arguments
+    ///
+    /// * `validator` - Validation closure that accepts a trimmed entry, and returns
+    ///   Ok if valid, or a string error to show if invalid.
+    pub fn with_validator<F>(mut self, validator: F) -> ListPrompt
+    where
+        F: Fn(&str) -> std::result::Result<(), String> + Send + 'static,
+    {
+        self.validator = Some(Box::new(validator));
+        self
+    }
+
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> ListPrompt {
+        self.text = self.text.with_output(output);
+        self
+    }
+
+    /// Give up waiting for an answer after some time
+    ///
+    /// Running the prompt then fails with `PromptError::Timeout`,
+    /// rather than splitting whatever was typed so far.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> ListPrompt {
+        self.text = self.text.with_timeout(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> ListPrompt {
+        self.text = self.text.with_countdown();
+        self
+    }
+
+    /// Splits the input into trimmed entries and validates each of them
+    ///
+    /// Input that is empty or only whitespace has no entries.
+    fn split(&self, input: &str) -> std::result::Result<Vec<String>, String> {
+        if input.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let entries = input
+            .split(self.separator.as_str())
+            .map(str::trim)
+            .filter(|entry| !(self.skip_empty && entry.is_empty()));
+        let mut list = Vec::new();
+        for (i, entry) in entries.enumerate() {
+            if let Some(validator) = &self.validator {
+                validator(entry).map_err(|e| format!("Entry {} \"{}\": {}", i + 1, entry, e))?;
+            }
+            list.push(entry.to_string());
+        }
+        Ok(list)
+    }
+}
+impl Prompt<Vec<String>> for ListPrompt {
+    fn line_question(&self) -> Option<String> {
+        self.text.line_question()
+    }
+    /// Accepts any line whose entries all pass the validator
+    fn parse_line(&mut self, line: &str) -> std::result::Result<Vec<String>, String> {
+        self.value = self.split(line)?;
+        self.text.input = TextInput::new(line);
+        Ok(self.result())
+    }
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        if self.separator.is_empty() {
+            return Err(PromptError::InvalidConfiguration(
+                "the separator is empty".to_string(),
+            ));
+        }
+        Ok(())
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        self.text.core()
+    }
+    fn validate(&mut self) -> std::result::Result<(), String> {
+        self.value = self.split(self.text.input.as_str())?;
+        Ok(())
+    }
+    fn result(&self) -> Vec<String> {
+        self.value.clone()
+    }
+    fn display(&mut self) -> crossterm::Result<()> {
+        self.text.display()
+    }
+    fn handle_key_event(&mut self, event: KeyEvent) {
+        self.text.handle_key_event(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::VirtualTerminal,
+        utils::{key_events, typed},
+    };
+    #[cfg(feature = "async")]
+    use futures::executor::block_on;
+    use std::io::sink;
+    use std::time::Instant;
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run_with_typed_input() {
+        let mut prompt = TextPrompt::new("Name?").with_output(Output::new(sink()));
+        let mut events = typed("jasmin");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = block_on(prompt.run_with_events(key_events(events)));
+        assert_eq!(result.unwrap(), "jasmin");
+    }
+
+    #[test]
+    fn run_keeps_going_until_valid() {
+        let mut prompt = TextPrompt::new("Name?")
+            .with_validator(|input| match input.len() {
+                0 => Err("You must type something!".to_string()),
+                _ => Ok(()),
+            })
+            .with_output(Output::new(sink()));
+        let mut events = vec![KeyEvent::from(KeyCode::Enter)];
+        events.extend(typed("x"));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), "x");
+    }
+
+    #[test]
+    fn run_with_capturing_validator() {
+        let taken: Vec<String> = "admin\nroot".lines().map(String::from).collect();
+        let mut prompt = TextPrompt::new("Username?")
+            .with_validator(move |input| match taken.iter().any(|name| name == input) {
+                true => Err(format!("{} is taken", input)),
+                false => Ok(()),
+            })
+            .with_output(Output::new(sink()));
+        let mut events = typed("root");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        events.push(KeyEvent::from(KeyCode::Char('s')));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), "roots");
+    }
+
+    #[test]
+    fn run_fails_when_events_run_out() {
+        let mut prompt = TextPrompt::new("Name?").with_output(Output::new(sink()));
+        let result = prompt.run_blocking_with_events(key_events(typed("abc")));
+        match result {
+            Err(PromptError::Eof) => {}
+            _ => panic!("expected an Eof error"),
+        }
+    }
+
+    #[test]
+    fn display_cursor_column() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Name?").with_output(terminal.output());
+        prompt.display().unwrap();
+        for event in typed("abc") {
+            prompt.handle_key_event(event);
+        }
+        prompt.handle_key_event(KeyEvent::from(KeyCode::Left));
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Name? › abc");
+        assert_eq!(screen.cursor, (12, 0));
+    }
+
+    #[test]
+    fn display_cursor_column_with_wide_message() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("名前?").with_output(terminal.output());
+        prompt.display().unwrap();
+        for event in typed("ab") {
+            prompt.handle_key_event(event);
+        }
+        prompt.handle_key_event(KeyEvent::from(KeyCode::Left));
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? 名前? › ab");
+        assert_eq!(screen.cursor, (11, 0));
+    }
+
+    #[test]
+    fn run_edits_by_grapheme() {
+        let mut prompt = TextPrompt::new("Name?").with_output(Output::new(sink()));
+        let mut events = typed("ne\u{301}e");
+        events.push(KeyEvent::from(KeyCode::Left));
+        events.push(KeyEvent::from(KeyCode::Left));
+        events.extend(typed("x"));
+        events.push(KeyEvent::from(KeyCode::Right));
+        events.push(KeyEvent::from(KeyCode::Backspace));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), "nxe");
+    }
+
+    #[test]
+    fn display_password_per_grapheme() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Password?")
+            .with_style(Style::Password)
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+        for event in typed("pa\u{308}ss") {
+            prompt.handle_key_event(event);
+        }
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Password? › ****");
+        assert_eq!(screen.cursor, (18, 0));
+    }
+
+    #[test]
+    fn display_countdown() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Name?")
+            .with_timeout(Duration::from_secs(3))
+            .with_countdown()
+            .with_output(terminal.output());
+        prompt.core.deadline = Some(Instant::now() + Duration::from_millis(2500));
+        prompt.display().unwrap();
+        for event in typed("ab") {
+            prompt.handle_key_event(event);
+        }
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Name? (3s) › ab");
+        assert_eq!(screen.cell(9, 0).style.foreground, Some(Color::DarkGrey));
+        assert_eq!(screen.cursor, (17, 0));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn dropped_run_shows_prompt_aborted() {
+        use futures::{poll, stream, StreamExt};
+
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Name?")
+            .with_validator(|_| Err("Nope".to_string()))
+            .with_output(terminal.output());
+        let mut events = typed("a");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let events = StreamExt::chain(key_events(events), stream::pending());
+        block_on(async {
+            let mut run = prompt.run_with_events(events);
+            assert!(poll!(&mut run).is_pending());
+        });
+
+        assert_eq!(prompt.core.state, PromptState::Aborted);
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "✖ Name?");
+        assert_eq!(screen.cursor, (0, 1));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run_ignores_keys_while_validating() {
+        use futures::{stream, StreamExt};
+        use futures_timer::Delay;
+
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Name?")
+            .with_async_validator(|_| {
+                Validation::new(async {
+                    Delay::new(Duration::from_millis(20)).await;
+                    Ok(())
+                })
+            })
+            .with_output(terminal.output());
+        let mut events = typed("ab");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        events.push(KeyEvent::from(KeyCode::Backspace));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let events = StreamExt::chain(key_events(events), stream::pending());
+        let result = block_on(prompt.run_with_events(events));
+        assert_eq!(result.unwrap(), "ab");
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.text() == "? Name? › ab\n› validating…"));
+    }
+
+    #[cfg(feature = "async")]
+    fn check_name(input: String) -> Validation {
+        Validation::new(async move {
+            match input.as_str() {
+                "taken" => Err("Name is taken".to_string()),
+                _ => Ok(()),
+            }
+        })
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run_blocking_shows_async_validation_error() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Name?")
+            .with_async_validator(check_name)
+            .with_output(terminal.output());
+        let mut events = typed("taken");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = prompt.run_blocking_with_events(key_events(events.clone()));
+        assert!(result.is_err());
+        assert_eq!(terminal.screen().text(), "? Name? › taken\n› Name is taken");
+
+        events.push(KeyEvent::from(KeyCode::Char('!')));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let mut prompt = TextPrompt::new("Name?")
+            .with_async_validator(check_name)
+            .with_output(Output::new(sink()));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), "taken!");
+    }
+
+    #[test]
+    fn display_validation_error() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Name?")
+            .with_validator(|_| Err("Nope".to_string()))
+            .with_output(terminal.output());
+        let events = vec![
+            KeyEvent::from(KeyCode::Char('a')),
+            KeyEvent::from(KeyCode::Enter),
+        ];
+        assert!(prompt.run_blocking_with_events(key_events(events)).is_err());
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Name? › a\n› Nope");
+        assert!(screen.cell(2, 1).style.italic);
+        assert_eq!(screen.cursor, (11, 0));
+    }
+
+    #[test]
+    fn display_wraps_wide_characters_like_the_terminal() {
+        // "名" doesn't fit in the last column, so the terminal moves it to the next row
+        let terminal = VirtualTerminal::new(10, 5);
+        let mut prompt = TextPrompt::new("Name").with_output(terminal.output());
+        prompt.display().unwrap();
+        for event in typed("名a名名aa名名") {
+            prompt.handle_key_event(event);
+            prompt.display().unwrap();
+        }
+        let typed = terminal.screen();
+        assert_eq!(typed.text(), "? Name ›\n名a名名aa\n名名");
+        assert_eq!(typed.cursor, (4, 2));
+
+        let terminal = VirtualTerminal::new(10, 5);
+        let mut prompt = TextPrompt::new("Name").with_output(terminal.output());
+        prompt.input = TextInput::new("名a名名aa名名");
+        prompt.display().unwrap();
+        assert_eq!(terminal.screen(), typed);
+    }
+
+    #[test]
+    fn display_multi_line_message() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = TextPrompt::new("Welcome!\nName?")
+            .with_validator(|_| Err("Nope".to_string()))
+            .with_output(terminal.output());
+        let mut events = typed("ab");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        events.push(KeyEvent::from(KeyCode::Backspace));
+        assert!(prompt.run_blocking_with_events(key_events(events)).is_err());
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Welcome!\nName? › a\n› Nope");
+        assert_eq!(screen.cursor, (9, 1));
+    }
+
+    #[test]
+    #[cfg(feature = "async")]
+    fn run_lines_validates_async() {
+        let mut prompt = TextPrompt::new("Name?")
+            .with_async_validator(check_name)
+            .with_output(Output::new(sink()));
+        assert_eq!(
+            prompt.run_lines("taken\nfree\n".as_bytes()).unwrap(),
+            "free"
+        );
+    }
+
+    #[test]
+    fn run_lines_validates() {
+        let mut prompt = TextPrompt::new("Name?")
+            .with_validator(|input| match input.len() {
+                0 => Err("You must type something!".to_string()),
+                _ => Ok(()),
+            })
+            .with_output(Output::new(sink()));
+        assert_eq!(
+            prompt.run_lines("\r\njasmin\r\n".as_bytes()).unwrap(),
+            "jasmin"
+        );
+    }
+
+    fn port_prompt() -> ParsePrompt<u16> {
+        ParsePrompt::new("Port?").with_validator(|port| match *port >= 1024 {
+            true => Ok(()),
+            false => Err("Reserved".to_string()),
+        })
+    }
+
+    #[test]
+    fn parse_keeps_going_until_valid() {
+        let terminal = VirtualTerminal::new(40, 5);
+        let mut prompt = port_prompt().with_output(terminal.output());
+        let mut events = typed("80");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        events.extend(typed("x"));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        assert!(prompt
+            .run_blocking_with_events(key_events(events.clone()))
+            .is_err());
+        assert!(terminal
+            .frames()
+            .iter()
+            .any(|frame| frame.text() == "? Port? › 80\n› Reserved"));
+        assert_eq!(
+            terminal.screen().text(),
+            "? Port? › 80x\n› invalid digit found in string"
+        );
+
+        events.push(KeyEvent::from(KeyCode::Backspace));
+        events.extend(typed("80"));
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let mut prompt = port_prompt().with_output(Output::new(sink()));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), 8080);
+    }
+
+    #[test]
+    fn parse_run_lines() {
+        let mut prompt = port_prompt().with_output(Output::new(sink()));
+        let result = prompt.run_lines("http\r\n22\r\n8080\r\n".as_bytes());
+        assert_eq!(result.unwrap(), 8080);
+    }
+
+    fn hosts_prompt() -> ListPrompt {
+        ListPrompt::new("Hosts?").with_validator(|host| match host.contains('.') {
+            true => Ok(()),
+            false => Err("Not a domain".to_string()),
+        })
+    }
+
+    #[test]
+    fn list_reports_failing_entry() {
+        let terminal = VirtualTerminal::new(50, 5);
+        let mut prompt = hosts_prompt().with_output(terminal.output());
+        let mut events = typed("a.io, b");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        assert!(prompt.run_blocking_with_events(key_events(events)).is_err());
+        assert_eq!(
+            terminal.screen().text(),
+            "? Hosts? › a.io, b\n› Entry 2 \"b\": Not a domain"
+        );
+
+        let mut events = typed(".io");
+        events.push(KeyEvent::from(KeyCode::Enter));
+        let result = prompt.run_blocking_with_events(key_events(events));
+        assert_eq!(result.unwrap(), vec!["a.io", "b.io"]);
+    }
+
+    #[test]
+    fn list_run_lines_splits_and_trims() {
+        let mut prompt = hosts_prompt().with_output(Output::new(sink()));
+        let result = prompt.run_lines("a.io,,b.io\r\n a.io , b.io \r\n".as_bytes());
+        assert_eq!(result.unwrap(), vec!["a.io", "b.io"]);
+
+        let mut prompt = ListPrompt::new("Tags?")
+            .with_separator(";")
+            .without_empty()
+            .with_output(Output::new(sink()));
+        let result = prompt.run_lines("rust; cli;;, tui ;\r\n".as_bytes());
+        assert_eq!(result.unwrap(), vec!["rust", "cli", ", tui"]);
+        let result = prompt.run_lines("  \r\n".as_bytes());
+        assert_eq!(result.unwrap(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,300 @@
+//! Interactive prompt where the user switches between two labelled answers
+
+use crate::{
+    error::PromptError,
+    utils::{
+        is_abort_event, print_input_icon, print_state_icon, Frame, Output, PromptCore, PromptState,
+    },
+    Prompt,
+};
+use crossterm::{
+    cursor,
+    event::{KeyCode, KeyEvent, KeyModifiers},
+    queue,
+    style::{style, Attribute, Color},
+};
+use std::fmt;
+use std::io::Write;
+use std::time::Duration;
+
+/// Interactive prompt where the user switches between two labelled answers
+///
+/// Shows both labels side by side, e.g. "on / off", and highlights the current answer.
+/// Use <kbd>left</kbd>/<kbd>right</kbd> to pick one, <kbd>tab</kbd> or <kbd>space</kbd>
+/// to switch between them and <kbd>enter</kbd> to submit.
+/// The left (active) label stands for `true`, the right (inactive) one for `false`.
+///
+/// # Examples
+///
+/// ```
+/// use prompts::{error::PromptError, toggle::TogglePrompt, Prompt};
+/// # #[cfg(feature = "async")]
+/// # async fn run() {
+/// let mut prompt = TogglePrompt::new("Where to deploy?").with_labels("prod", "staging");
+///
+/// match prompt.run().await {
+///     Ok(true) => println!("Deploying to production!"),
+///     Ok(false) => println!("Deploying to staging!"),
+///     Err(PromptError::Cancelled) => println!("Prompt was cancelled!"),
+///     Err(e) => println!("Prompt failed: {}", e),
+/// }
+/// # }
+/// ```
+pub struct TogglePrompt {
+    message: String,
+    core: PromptCore,
+    value: bool,
+    active: String,
+    inactive: String,
+}
+impl fmt::Debug for TogglePrompt {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("TogglePrompt")
+            .field("message", &self.message)
+            .field("value", &self.value)
+            .field("active", &self.active)
+            .field("inactive", &self.inactive)
+            .finish()
+    }
+}
+impl TogglePrompt {
+    /// Returns a TogglePrompt ready to be run, labelled "on" and "off"
+    ///
+    /// # Arguments
+    ///
+    /// * `message` - The message to display to the user before the prompt
+    pub fn new<S>(message: S) -> TogglePrompt
+    where
+        S: Into<String>,
+    {
+        TogglePrompt {
+            message: message.into(),
+            core: PromptCore::default(),
+            value: false,
+            active: "on".to_string(),
+            inactive: "off".to_string(),
+        }
+    }
+
+    /// Set the labels of the two answers
+    ///
+    /// # Arguments
+    ///
+    /// * `active` - The label shown on the left, which submits `true`
+    /// * `inactive` - The label shown on the right, which submits `false`
+    pub fn with_labels<S, U>(mut self, active: S, inactive: U) -> TogglePrompt
+    where
+        S: Into<String>,
+        U: Into<String>,
+    {
+        self.active = active.into();
+        self.inactive = inactive.into();
+        self
+    }
+
+    /// Set the initial answer, `false` unless set
+    pub fn set_initial(mut self, initial: bool) -> TogglePrompt {
+        self.value = initial;
+        self
+    }
+
+    /// Render the prompt somewhere other than stdout
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output to render to
+    pub fn with_output(mut self, output: Output) -> TogglePrompt {
+        self.core.output = output;
+        self
+    }
+
+    /// Give up waiting for an answer after some time
+    ///
+    /// When it expires the highlighted answer is submitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - How long the user has to answer
+    pub fn with_timeout(mut self, timeout: Duration) -> TogglePrompt {
+        self.core.timeout = Some(timeout);
+        self
+    }
+
+    /// Show how many seconds are left before the timeout next to the message
+    pub fn with_countdown(mut self) -> TogglePrompt {
+        self.core.countdown = true;
+        self
+    }
+
+    /// Returns the label of the current answer
+    fn label(&self) -> &str {
+        if self.value {
+            &self.active
+        } else {
+            &self.inactive
+        }
+    }
+}
+impl Prompt<bool> for TogglePrompt {
+    fn line_question(&self) -> Option<String> {
+        Some(format!(
+            "{} ({}/{})",
+            self.message, self.active, self.inactive
+        ))
+    }
+    /// Accepts either label (ignoring case), or an empty line for the initial answer
+    fn parse_line(&mut self, line: &str) -> std::result::Result<bool, String> {
+        self.value = match line.trim().to_lowercase() {
+            line if line.is_empty() => self.value,
+            line if line == self.active.to_lowercase() => true,
+            line if line == self.inactive.to_lowercase() => false,
+            _ => {
+                return Err(format!(
+                    "Please answer {} or {}",
+                    self.active, self.inactive
+                ))
+            }
+        };
+        Ok(self.value)
+    }
+    /// Rejects labels that only differ in case, as `parse_line` couldn't tell them apart
+    fn check(&self) -> std::result::Result<(), PromptError> {
+        if self.active.to_lowercase() == self.inactive.to_lowercase() {
+            return Err(PromptError::InvalidConfiguration(format!(
+                "both answers are labelled {}",
+                self.active
+            )));
+        }
+        Ok(())
+    }
+    fn core(&mut self) -> &mut PromptCore {
+        &mut self.core
+    }
+    /// Submits the highlighted answer
+    fn submit_default(&mut self) -> bool {
+        true
+    }
+    fn result(&self) -> bool {
+        self.value
+    }
+    fn display(&mut self) -> crossterm::Result<()> {
+        if self.core.state == PromptState::Created {
+            queue!(self.core.output, cursor::Hide)?;
+            self.core.state = PromptState::Running;
+        }
+
+        let mut frame = Frame::new();
+        frame.print_styled(print_state_icon(&self.core.state).0);
+        frame.print(" ");
+        frame.print_styled(style(&self.message).attribute(Attribute::Bold));
+        self.core.print_countdown(&mut frame);
+        frame.print(" ");
+        frame.print_styled(print_input_icon(&self.core.state).0);
+        if !self.core.state.is_done() {
+            let label = |text: &str, current: bool| {
+                if current {
+                    style(text.to_string())
+                        .with(Color::Cyan)
+                        .attribute(Attribute::Underlined)
+                } else {
+                    style(text.to_string())
+                }
+            };
+            frame.print_styled(label(&self.active, self.value));
+            frame.print_styled(style(" / ").with(Color::DarkGrey));
+            frame.print_styled(label(&self.inactive, !self.value));
+        }
+        if self.core.state == PromptState::Success {
+            frame.print(self.label());
+        }
+        if self.core.state.is_done() {
+            frame.new_line();
+            queue!(self.core.output, cursor::Show)?;
+        }
+        self.core.render(&frame)
+    }
+    fn handle_key_event(&mut self, event: KeyEvent) {
+        if is_abort_event(event) {
+            self.core.state = PromptState::Aborted;
+            return;
+        }
+        if event.modifiers == KeyModifiers::empty() {
+            match event.code {
+                KeyCode::Enter => self.core.state = PromptState::Success,
+                KeyCode::Left | KeyCode::Char('h') => self.value = true,
+                KeyCode::Right | KeyCode::Char('l') => self.value = false,
+                KeyCode::Tab | KeyCode::Char(' ') => self.value = !self.value,
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{testing::VirtualTerminal, utils::key_events};
+    use std::io::sink;
+
+    #[test]
+    fn run_switches_between_labels() {
+        let mut prompt = TogglePrompt::new("Dark mode?").with_output(Output::new(sink()));
+        let events = vec![
+            KeyEvent::from(KeyCode::Left),
+            KeyEvent::from(KeyCode::Tab),
+            KeyEvent::from(KeyCode::Char(' ')),
+            KeyEvent::from(KeyCode::Enter),
+        ];
+        assert!(prompt.run_blocking_with_events(key_events(events)).unwrap());
+
+        let mut prompt = TogglePrompt::new("Dark mode?")
+            .set_initial(true)
+            .with_output(Output::new(sink()));
+        let events = vec![
+            KeyEvent::from(KeyCode::Right),
+            KeyEvent::from(KeyCode::Enter),
+        ];
+        assert!(!prompt.run_blocking_with_events(key_events(events)).unwrap());
+    }
+
+    #[test]
+    fn display_highlights_current_label() {
+        let terminal = VirtualTerminal::new(50, 5);
+        let mut prompt = TogglePrompt::new("Target?")
+            .with_labels("prod", "staging")
+            .with_output(terminal.output());
+        prompt.display().unwrap();
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Target? › prod / staging");
+        assert!(!screen.cell(12, 0).style.underlined);
+        assert!(screen.cell(19, 0).style.underlined);
+        assert_eq!(screen.cell(19, 0).style.foreground, Some(Color::Cyan));
+        assert!(!screen.cursor_visible);
+
+        let events = vec![KeyEvent::from(KeyCode::Tab), KeyEvent::from(KeyCode::Enter)];
+        assert!(prompt.run_blocking_with_events(key_events(events)).unwrap());
+        assert_eq!(terminal.screen().text(), "✔ Target? … prod");
+    }
+
+    #[test]
+    fn run_with_same_labels() {
+        let mut prompt = TogglePrompt::new("Target?")
+            .with_labels("prod", "Prod")
+            .with_output(Output::new(sink()));
+        match prompt.run_lines("prod\n".as_bytes()) {
+            Err(PromptError::InvalidConfiguration(_)) => {}
+            _ => panic!("expected an InvalidConfiguration error"),
+        }
+    }
+
+    #[test]
+    fn run_lines_accepts_labels() {
+        let mut prompt = TogglePrompt::new("Target?")
+            .with_labels("Prod", "Staging")
+            .with_output(Output::new(sink()));
+        assert!(prompt.run_lines("maybe\nprod\n".as_bytes()).unwrap());
+        assert!(prompt.run_lines("\n".as_bytes()).unwrap());
+        assert!(!prompt.run_lines(" STAGING \n".as_bytes()).unwrap());
+    }
+}
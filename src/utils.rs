@@ -2,11 +2,29 @@
 //!
 //! Public in case you want to implement your own custom prompts
 
+use crate::error::PromptError;
 use crossterm::{
-    event::{KeyCode, KeyEvent, KeyModifiers},
-    style::{style, Color, PrintStyledContent},
+    cursor,
+    event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers},
+    queue,
+    style::{style, Attribute, Color, Print, PrintStyledContent, StyledContent},
+    terminal::{disable_raw_mode, enable_raw_mode, size as terminal_size, Clear, ClearType},
+};
+#[cfg(feature = "async")]
+use futures::{
+    task::{Context, Poll},
+    Future, Stream,
 };
 use std::cmp;
+use std::fmt;
+use std::io::{self, stderr, stdout, Write};
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use std::vec;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Figures that are used for the prompts
 pub enum Figures {
@@ -61,48 +79,650 @@ impl Figures {
 }
 
 /// Internal state of a prompt
-#[derive(Eq, PartialEq, Debug)]
+#[derive(Eq, PartialEq, Debug, Default)]
 pub enum PromptState {
     /// Prompt was just created (and has not yet been displayed for the first time)
+    #[default]
     Created,
     /// Prompt is running/displaying
     Running,
     /// Prompt input needs validation
     Validate,
+    /// Prompt input is being validated in the background, see `Prompt::validate_async`
+    Validating,
     /// The prompt was aborted by the user
     Aborted,
     /// The prompt completed successfully
     Success,
 }
-impl Default for PromptState {
-    fn default() -> PromptState {
-        PromptState::Created
-    }
-}
 impl PromptState {
     pub fn is_done(&self) -> bool {
         *self == PromptState::Aborted || *self == PromptState::Success
     }
 }
 
+/// Where a prompt renders itself
+///
+/// Defaults to stdout. Use `Output::stderr` to keep prompts out of piped
+/// stdout data, or `Output::new` to render into any other writer.
+/// Cloning an Output shares the underlying writer.
+#[derive(Clone)]
+pub struct Output {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    size: Option<(u16, u16)>,
+}
+impl Output {
+    /// Returns an Output that writes to the given writer
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Anything implementing `std::io::Write`
+    pub fn new<W>(writer: W) -> Output
+    where
+        W: Write + Send + 'static,
+    {
+        Output {
+            writer: Arc::new(Mutex::new(Box::new(writer))),
+            size: None,
+        }
+    }
+
+    /// Returns an Output that writes to stdout
+    pub fn stdout() -> Output {
+        Output::new(stdout())
+    }
+
+    /// Returns an Output that writes to stderr
+    pub fn stderr() -> Output {
+        Output::new(stderr())
+    }
+
+    /// Use a fixed size instead of asking the terminal
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - Width of the output
+    /// * `rows` - Height of the output
+    pub fn with_size(mut self, columns: u16, rows: u16) -> Output {
+        self.size = Some((columns, rows));
+        self
+    }
+
+    /// Updates the size after the terminal was resized
+    ///
+    /// Only outputs with a fixed size are affected, others ask the terminal every time.
+    ///
+    /// # Arguments
+    ///
+    /// * `columns` - New width of the output
+    /// * `rows` - New height of the output
+    pub fn resize(&mut self, columns: u16, rows: u16) {
+        if self.size.is_some() {
+            self.size = Some((columns, rows));
+        }
+    }
+
+    /// Returns the size (columns, rows) of the terminal
+    ///
+    /// Falls back to 80x24 if the size can not be determined,
+    /// e.g. because stdout is redirected.
+    pub fn size(&self) -> (u16, u16) {
+        match self.size {
+            Some(size) => size,
+            None => terminal_size().unwrap_or((80, 24)),
+        }
+    }
+}
+impl Default for Output {
+    fn default() -> Output {
+        Output::stdout()
+    }
+}
+impl fmt::Debug for Output {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("Output")
+            .field("size", &self.size)
+            .finish()
+    }
+}
+impl Write for Output {
+    // A panic while writing must not stop the TerminalGuard from restoring the terminal,
+    // so a poisoned lock is used anyway
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.writer.lock() {
+            Ok(mut writer) => writer.write(buf),
+            Err(poisoned) => poisoned.into_inner().write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self.writer.lock() {
+            Ok(mut writer) => writer.flush(),
+            Err(poisoned) => poisoned.into_inner().flush(),
+        }
+    }
+}
+
+/// The part of a prompt that the shared driver works with
+///
+/// Every prompt holds one and hands it out through `Prompt::core`.
+#[derive(Debug, Default)]
+pub struct PromptCore {
+    /// Where the prompt is in its lifecycle
+    pub state: PromptState,
+    /// Where the prompt renders to
+    pub output: Output,
+    /// Why the last answer was rejected by `Prompt::validate`, if it was
+    pub error: Option<String>,
+    /// Whether to capture the mouse and pass its events to `Prompt::handle_mouse_event`
+    pub mouse: bool,
+    /// Screen row of the first line of the prompt, to find what the mouse points at
+    pub top_row: u16,
+    /// Keeps track of what the prompt shows on screen
+    pub renderer: Renderer,
+    /// How long the user has to answer, see `Prompt::submit_default`
+    pub timeout: Option<Duration>,
+    /// Whether to show the seconds left before the timeout
+    pub countdown: bool,
+    /// When the prompt times out, set by the driver when it starts the prompt
+    pub deadline: Option<Instant>,
+    /// The validation running in the background while in `PromptState::Validating`
+    #[cfg(feature = "async")]
+    pub validation: Option<Validation>,
+}
+impl PromptCore {
+    /// Replaces what the prompt showed before with the given frame
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame to show
+    pub fn render(&mut self, frame: &Frame) -> crossterm::Result<()> {
+        self.renderer.render(&mut self.output, frame)?;
+        // Printing past the bottom of the terminal scrolls it, moving the prompt up
+        let screen_rows = self.output.size().1;
+        self.top_row = cmp::min(
+            self.top_row,
+            screen_rows.saturating_sub(self.renderer.height),
+        );
+        Ok(())
+    }
+
+    /// Adds the seconds left before the timeout to the frame, if a countdown is shown
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame to add the countdown to, usually right after the message
+    pub fn print_countdown(&self, frame: &mut Frame) {
+        if let (true, Some(deadline)) = (self.countdown, self.deadline) {
+            if !self.state.is_done() {
+                let left = deadline.saturating_duration_since(Instant::now());
+                let seconds = left.as_millis().div_ceil(1000);
+                frame.print_styled(style(format!(" ({}s)", seconds)).with(Color::DarkGrey));
+            }
+        }
+    }
+
+    /// Returns which line of the last frame is shown on the given screen row
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Screen row, e.g. of a mouse event
+    pub fn line_at(&self, row: u16) -> Option<usize> {
+        self.renderer.line_at(row.checked_sub(self.top_row)?)
+    }
+}
+
+/// A frame of a prompt: lines of styled text, and where the cursor goes
+///
+/// Build one in `Prompt::display` and show it with `PromptCore::render`,
+/// which takes care of lines wrapping in the terminal.
+#[derive(Debug)]
+pub struct Frame {
+    lines: Vec<Line>,
+    cursor: Option<(usize, usize)>,
+}
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Line {
+    /// Text including escape codes for styling
+    content: String,
+    /// The text without the escape codes
+    text: String,
+}
+impl Line {
+    /// Number of rows the line takes up in a terminal this wide
+    fn rows(&self, columns: usize) -> u16 {
+        wrap(&self.text, self.text.len(), columns).row as u16 + 1
+    }
+}
+
+/// Where a character of text ends up in the terminal, see `wrap`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Wrapped {
+    /// Row, counted from the row the text starts on
+    row: usize,
+    /// Column, which is one past the last column if the text ends there
+    column: usize,
+    /// Whether a wide character was moved to the next row before it, leaving a cell blank
+    skipped: bool,
+}
+
+/// Lays out text starting at the first column like a terminal this wide does
+///
+/// A terminal moves a wide character that doesn't fit in the last column
+/// to the next row, so rows can't be counted from the width of the text alone.
+/// Returns where the character at byte `offset` of the text goes,
+/// or where the text ends if `offset` is its length.
+fn wrap(text: &str, offset: usize, columns: usize) -> Wrapped {
+    let mut wrapped = Wrapped {
+        row: 0,
+        column: 0,
+        skipped: false,
+    };
+    for (i, c) in text.char_indices() {
+        let width = c.width().unwrap_or(0);
+        if width == 0 {
+            continue;
+        }
+        if wrapped.column + width > columns {
+            wrapped.skipped |= wrapped.column < columns;
+            wrapped.row += 1;
+            wrapped.column = 0;
+        }
+        if i >= offset {
+            break;
+        }
+        wrapped.column += width;
+    }
+    wrapped
+}
+impl Frame {
+    /// Returns a frame with one empty line
+    pub fn new() -> Frame {
+        Frame {
+            lines: vec![Line::default()],
+            cursor: None,
+        }
+    }
+
+    /// Adds unstyled text, where `\n` starts a new line
+    pub fn print<D: fmt::Display>(&mut self, text: D) {
+        self.print_styled(style(text.to_string()));
+    }
+
+    /// Adds styled text, where `\n` starts a new line
+    pub fn print_styled<D: fmt::Display + Clone>(&mut self, content: StyledContent<D>) {
+        let text = content.content().to_string();
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.new_line();
+            }
+            if !part.is_empty() {
+                let line = self.lines.last_mut().unwrap();
+                line.content += &StyledContent::new(content.style().clone(), part).to_string();
+                line.text += part;
+            }
+        }
+    }
+
+    /// Starts a new line
+    pub fn new_line(&mut self) {
+        self.lines.push(Line::default());
+    }
+
+    /// Puts the cursor right after the text added so far
+    ///
+    /// Otherwise the cursor is left at the end of the frame.
+    pub fn set_cursor(&mut self) {
+        let line = self.lines.len() - 1;
+        self.cursor = Some((line, self.lines[line].text.len()));
+    }
+
+    /// Number of rows the frame takes up in a terminal this wide, including wrapped lines
+    pub fn rows(&self, columns: u16) -> u16 {
+        let columns = cmp::max(columns, 1) as usize;
+        self.lines.iter().map(|line| line.rows(columns)).sum()
+    }
+}
+impl Default for Frame {
+    fn default() -> Frame {
+        Frame::new()
+    }
+}
+
+/// Shows the frames of a prompt, each one replacing the one before
+///
+/// Remembers the lines of the last frame, how many rows each took up after
+/// wrapping and where it left the cursor. The next frame then only rewrites
+/// the lines that changed, and everything below the first line that takes
+/// up a different number of rows, in a single write to the output.
+/// This avoids flicker on slow terminals.
+#[derive(Debug, Default)]
+pub struct Renderer {
+    /// Lines of the last frame
+    lines: Vec<Line>,
+    /// Width of the terminal the last frame was laid out for
+    columns: usize,
+    /// Row each line of the last frame started on, counted from its first row
+    line_rows: Vec<u16>,
+    /// Number of rows the last frame took up
+    height: u16,
+    /// Row the cursor was left on, counted from the first row of the last frame
+    cursor_row: u16,
+}
+impl Renderer {
+    /// Replaces the last frame with the given one, rewriting only what changed
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Where to render to
+    /// * `frame` - The frame to show
+    pub fn render(&mut self, output: &mut Output, frame: &Frame) -> crossterm::Result<()> {
+        let columns = cmp::max(output.size().0, 1) as usize;
+        // Written in one go, as every write may be a round trip, e.g. over SSH
+        let mut buffer = Vec::new();
+        let mut line_rows = Vec::with_capacity(frame.lines.len());
+        let mut height = 0;
+        for line in &frame.lines {
+            line_rows.push(height);
+            height += line.rows(columns);
+        }
+
+        // Lines before `kept` stay where they were, so changed ones can be rewritten in place
+        let kept = match columns == self.columns {
+            true => frame
+                .lines
+                .iter()
+                .zip(&self.lines)
+                .take_while(|(new, old)| new.rows(columns) == old.rows(columns))
+                .count(),
+            false => 0,
+        };
+        let mut cursor = Cursor {
+            row: self.cursor_row,
+            // The row the cursor is on is ours, even before the first frame
+            shown: cmp::max(self.height, 1),
+        };
+        for (i, line) in frame.lines.iter().enumerate().take(kept) {
+            if *line == self.lines[i] {
+                continue;
+            }
+            let end = wrap(&line.text, line.text.len(), columns);
+            // Cells left blank by wide characters moving to the next row keep what was there
+            if end.skipped {
+                for row in line_rows[i]..=line_rows[i] + end.row as u16 {
+                    cursor.move_to_row(&mut buffer, row)?;
+                    queue!(buffer, Clear(ClearType::CurrentLine))?;
+                }
+            }
+            cursor.move_to_row(&mut buffer, line_rows[i])?;
+            queue!(buffer, cursor::MoveToColumn(0), Print(&line.content))?;
+            // Clearing right after the last column would erase the last character
+            if end.column < columns {
+                queue!(buffer, Clear(ClearType::UntilNewLine))?;
+            }
+            cursor.row = line_rows[i] + line.rows(columns) - 1;
+        }
+        if kept < frame.lines.len() || kept < self.lines.len() {
+            cursor.move_to_row(&mut buffer, line_rows.get(kept).cloned().unwrap_or(height))?;
+            queue!(
+                buffer,
+                cursor::MoveToColumn(0),
+                Clear(ClearType::FromCursorDown)
+            )?;
+            for (i, line) in frame.lines.iter().enumerate().skip(kept) {
+                if i > kept {
+                    queue!(buffer, Print("\n\r"))?;
+                }
+                queue!(buffer, Print(&line.content))?;
+            }
+            if kept < frame.lines.len() {
+                cursor.row = height - 1;
+            }
+        }
+
+        let (line, offset) = frame.cursor.unwrap_or_else(|| {
+            (
+                frame.lines.len() - 1,
+                frame.lines.last().unwrap().text.len(),
+            )
+        });
+        let wrapped = wrap(&frame.lines[line].text, offset, columns);
+        cursor.move_to_row(&mut buffer, line_rows[line] + wrapped.row as u16)?;
+        // MoveToColumn counts from 1
+        queue!(buffer, cursor::MoveToColumn(wrapped.column as u16 + 1))?;
+        output.write_all(&buffer)?;
+        output.flush()?;
+
+        self.lines = frame.lines.clone();
+        self.columns = columns;
+        self.line_rows = line_rows;
+        self.height = height;
+        self.cursor_row = cursor.row;
+        Ok(())
+    }
+
+    /// Forgets the last frame, for when the screen was cleared and the cursor moved to the top
+    pub fn reset(&mut self) {
+        *self = Renderer::default();
+    }
+
+    /// Returns which line of the last frame starts on or wraps onto the given row
+    ///
+    /// # Arguments
+    ///
+    /// * `row` - Row counted from the first row of the last frame
+    pub fn line_at(&self, row: u16) -> Option<usize> {
+        if row >= self.height {
+            return None;
+        }
+        self.line_rows.iter().rposition(|&start| start <= row)
+    }
+}
+
+/// Where the renderer left the cursor while rendering a frame
+struct Cursor {
+    /// Row the cursor is on, counted from the first row of the frame
+    row: u16,
+    /// Number of rows the prompt has printed so far
+    shown: u16,
+}
+impl Cursor {
+    /// Moves the cursor up or down to the given row, starting a new row below the prompt if needed
+    fn move_to_row(&mut self, output: &mut Vec<u8>, row: u16) -> crossterm::Result<()> {
+        // Moving down stops at the bottom of the screen, a new line scrolls it
+        let target = cmp::min(row, self.shown - 1);
+        // MoveUp(0) and MoveDown(0) still move a row
+        if target < self.row {
+            queue!(output, cursor::MoveUp(self.row - target))?;
+        } else if target > self.row {
+            queue!(output, cursor::MoveDown(target - self.row))?;
+        }
+        for _ in target..row {
+            queue!(output, Print("\n\r"))?;
+        }
+        self.row = row;
+        self.shown = cmp::max(self.shown, row + 1);
+        Ok(())
+    }
+}
+
+/// Puts the terminal in raw mode, and restores it when dropped
+///
+/// Restoring happens even if the prompt panics, returns early with an error
+/// or its future is dropped: raw mode is disabled, the cursor is shown again
+/// and, unless `finish` was called, whatever is left of the prompt below
+/// the cursor is cleared and the cursor moved to a fresh line.
+#[derive(Debug)]
+pub struct TerminalGuard {
+    output: Output,
+    finished: bool,
+    mouse: bool,
+}
+impl TerminalGuard {
+    /// Enables raw mode and returns a guard that will disable it again
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - The output the prompt renders to
+    pub fn new(output: Output) -> crossterm::Result<TerminalGuard> {
+        enable_raw_mode()?;
+        Ok(TerminalGuard {
+            output,
+            finished: false,
+            mouse: false,
+        })
+    }
+
+    /// Enables mouse capture, which is disabled again when the guard is dropped
+    pub fn capture_mouse(&mut self) -> crossterm::Result<()> {
+        queue!(self.output, EnableMouseCapture)?;
+        self.output.flush()?;
+        self.mouse = true;
+        Ok(())
+    }
+
+    /// Marks the prompt as finished, meaning it already left the cursor on a fresh line
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+}
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // Errors can't be reported from drop, restore as much as possible
+        if !self.finished {
+            let _ = queue!(self.output, Clear(ClearType::FromCursorDown), Print("\n\r"));
+        }
+        if self.mouse {
+            let _ = queue!(self.output, DisableMouseCapture);
+        }
+        let _ = queue!(self.output, cursor::Show);
+        let _ = self.output.flush();
+        let _ = disable_raw_mode();
+    }
+}
+
 /// Should we abort on this event
 ///
-/// Returns true on CTRL+c, CTRL+z and ESC
+/// Returns true on CTRL+c, CTRL+d and ESC
 pub fn is_abort_event(event: KeyEvent) -> bool {
+    abort_error(event).is_some()
+}
+
+/// The error a prompt should return when aborted by this event
+///
+/// `Interrupted` on CTRL+c, `Eof` on CTRL+d and `Cancelled` on ESC
+pub fn abort_error(event: KeyEvent) -> Option<PromptError> {
     match event {
         KeyEvent {
             modifiers: KeyModifiers::CONTROL,
             code: KeyCode::Char('c'),
-        } => true,
+        } => Some(PromptError::Interrupted),
         KeyEvent {
             modifiers: KeyModifiers::CONTROL,
             code: KeyCode::Char('d'),
-        } => true,
+        } => Some(PromptError::Eof),
         KeyEvent {
             modifiers,
             code: KeyCode::Esc,
-        } if modifiers == KeyModifiers::empty() => true,
-        _ => false,
+        } if modifiers == KeyModifiers::empty() => Some(PromptError::Cancelled),
+        _ => None,
+    }
+}
+
+/// Turns a prewritten sequence of key events into a source of events
+///
+/// Meant for `Prompt::run_with_events` and `Prompt::run_blocking_with_events`,
+/// to drive a prompt without a terminal.
+///
+/// # Arguments
+///
+/// * `events` - The key events to send, in order, or just their key codes
+pub fn key_events<I>(events: I) -> KeyEvents
+where
+    I: IntoIterator,
+    I::Item: Into<KeyEvent>,
+{
+    let events: Vec<KeyEvent> = events.into_iter().map(Into::into).collect();
+    KeyEvents(events.into_iter())
+}
+
+/// Returns the key presses that type the given text, e.g. to pass to `key_events`
+///
+/// # Arguments
+///
+/// * `text` - The text to type, one character per key press
+pub fn typed(text: &str) -> Vec<KeyEvent> {
+    text.chars()
+        .map(|c| KeyEvent::from(KeyCode::Char(c)))
+        .collect()
+}
+
+/// Prewritten sequence of key events, see `key_events`
+///
+/// Is both an `Iterator` and (with the `async` feature) a `Stream` of events.
+#[derive(Debug)]
+pub struct KeyEvents(vec::IntoIter<KeyEvent>);
+impl Iterator for KeyEvents {
+    type Item = crossterm::Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|event| Ok(Event::Key(event)))
+    }
+}
+#[cfg(feature = "async")]
+impl Stream for KeyEvents {
+    type Item = crossterm::Result<Event>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context) -> Poll<Option<Self::Item>> {
+        Poll::Ready(Iterator::next(&mut *self))
+    }
+}
+
+/// Check of an answer that runs in the background, see `Prompt::validate_async`
+///
+/// Resolves to Ok if the answer is valid, or an error message to show if it is not.
+#[cfg(feature = "async")]
+pub struct Validation(Pin<Box<dyn Future<Output = Result<(), String>> + Send>>);
+#[cfg(feature = "async")]
+impl Validation {
+    /// Returns a Validation that waits for the given future
+    ///
+    /// # Arguments
+    ///
+    /// * `future` - Future that checks the answer
+    pub fn new<F>(future: F) -> Validation
+    where
+        F: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        Validation(Box::pin(future))
+    }
+}
+#[cfg(feature = "async")]
+impl fmt::Debug for Validation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("Validation")
+    }
+}
+#[cfg(feature = "async")]
+impl Future for Validation {
+    type Output = Result<(), String>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.0.as_mut().poll(cx)
+    }
+}
+
+/// Finds the choice a line of input refers to, for answering without a terminal
+///
+/// Matches the text of a choice exactly, or else its (zero-based) index.
+/// Returns the index of the choice, or an error message to show.
+pub fn find_choice<T: fmt::Display>(choices: &[T], line: &str) -> Result<usize, String> {
+    let line = line.trim();
+    match choices.iter().position(|choice| choice.to_string() == line) {
+        Some(index) => Ok(index),
+        None => match line.parse::<usize>() {
+            Ok(index) if index < choices.len() => Ok(index),
+            _ => Err(format!("\"{}\" is not one of the choices", line)),
+        },
     }
 }
 
@@ -127,21 +747,316 @@ pub fn print_input_icon(state: &PromptState) -> PrintStyledContent<String> {
     )
 }
 
+/// Returns how many columns the text takes up in the terminal
+///
+/// Wide characters, like most CJK characters, take up two columns
+/// and combining characters none.
+pub fn text_width(text: &str) -> usize {
+    UnicodeWidthStr::width(text)
+}
+
+/// Text typed into a prompt, edited by grapheme cluster
+///
+/// A grapheme cluster is what the user sees as a single character,
+/// like "é" written as "e" plus a combining accent, or an emoji made up of
+/// several code points. The cursor never ends up in the middle of one.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+    /// Byte offset into text, always on a grapheme cluster boundary
+    cursor: usize,
+}
+impl TextInput {
+    /// Returns a TextInput holding the given text, with the cursor at the end
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The initial text
+    pub fn new<S>(text: S) -> TextInput
+    where
+        S: Into<String>,
+    {
+        let text = text.into();
+        TextInput {
+            cursor: text.len(),
+            text,
+        }
+    }
+
+    /// Returns the whole text
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the text before the cursor
+    pub fn before_cursor(&self) -> &str {
+        &self.text[..self.cursor]
+    }
+
+    /// Returns the text after the cursor
+    pub fn after_cursor(&self) -> &str {
+        &self.text[self.cursor..]
+    }
+
+    /// Returns the number of grapheme clusters in the text
+    pub fn graphemes(&self) -> usize {
+        self.text.graphemes(true).count()
+    }
+
+    /// Inserts a character at the cursor and moves the cursor past it
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Removes the grapheme cluster before the cursor
+    pub fn backspace(&mut self) {
+        let start = self.previous_boundary();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Removes the grapheme cluster after the cursor
+    pub fn delete(&mut self) {
+        let end = self.next_boundary();
+        self.text.replace_range(self.cursor..end, "");
+    }
+
+    /// Moves the cursor one grapheme cluster to the left
+    pub fn move_left(&mut self) {
+        self.cursor = self.previous_boundary();
+    }
+
+    /// Moves the cursor one grapheme cluster to the right
+    pub fn move_right(&mut self) {
+        self.cursor = self.next_boundary();
+    }
+
+    /// Moves the cursor to the start of the text
+    pub fn move_home(&mut self) {
+        self.cursor = 0;
+    }
+
+    /// Moves the cursor to the end of the text
+    pub fn move_end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    fn previous_boundary(&self) -> usize {
+        match self.text[..self.cursor].graphemes(true).next_back() {
+            Some(grapheme) => self.cursor - grapheme.len(),
+            None => self.cursor,
+        }
+    }
+
+    fn next_boundary(&self) -> usize {
+        match self.text[self.cursor..].graphemes(true).next() {
+            Some(grapheme) => self.cursor + grapheme.len(),
+            None => self.cursor,
+        }
+    }
+}
+
 /// Returns start and end-index for showing a limited amount of items
 ///
-/// Used for SelectPrompt and AutocompletePrompt
+/// Used by `ListView`
 pub fn calc_entries(current: usize, total: usize, limit: usize) -> (usize, usize) {
     let start_index = cmp::min(
-        total.checked_sub(limit).unwrap_or(0),
-        current.checked_sub(limit / 2).unwrap_or(0),
+        total.saturating_sub(limit),
+        current.saturating_sub(limit / 2),
     );
     let end_index = cmp::min(start_index + limit, total);
     (start_index, end_index)
 }
 
+/// Returns the index of the clicked entry, if any, for list prompts
+///
+/// Expects one entry per line.
+///
+/// # Arguments
+///
+/// * `line` - Line of the frame that was clicked, see `PromptCore::line_at`
+/// * `first_line` - Line of the frame the first visible entry is on
+/// * `start_index` and `end_index` - The visible entries, see `calc_entries`
+pub fn clicked_entry(
+    line: usize,
+    first_line: usize,
+    start_index: usize,
+    end_index: usize,
+) -> Option<usize> {
+    let index = start_index + line.checked_sub(first_line)?;
+    if index < end_index {
+        Some(index)
+    } else {
+        None
+    }
+}
+
+/// One entry of a list, see `ListView`
+pub struct ListEntry {
+    /// Shown between the scroll arrow and the text, e.g. whether the entry is selected
+    pub marker: Option<StyledContent<&'static str>>,
+    /// The text of the entry
+    pub text: String,
+}
+
+/// The entries of a list prompt, scrolled to keep the highlighted one in view
+///
+/// Shows each entry on its own line, with a pointer in front of the
+/// highlighted one and arrows where more entries are scrolled out of view.
+/// Only shows as many entries as fit on screen below what the frame holds
+/// already, counting rows taken up by wrapped lines, so the whole prompt
+/// stays on screen and can be re-rendered in place.
+/// Remembers which entries it showed last, to find out which one was clicked.
+#[derive(Debug)]
+pub struct ListView {
+    limit: usize,
+    /// Line of the frame the first entry was shown on
+    first_line: usize,
+    start_index: usize,
+    end_index: usize,
+}
+impl ListView {
+    /// Returns a ListView that shows at most `limit` entries at once
+    pub fn new(limit: usize) -> ListView {
+        ListView {
+            limit,
+            first_line: 0,
+            start_index: 0,
+            end_index: 0,
+        }
+    }
+
+    /// Adds the entries that fit on screen to the frame, each on a new line
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - The frame to add the entries to, holding what is shown above them
+    /// * `screen` - Columns and rows of the terminal, see `Output::size`
+    /// * `footer_rows` - Rows to keep free below the entries, e.g. for an error message
+    /// * `total` - How many entries there are
+    /// * `current` - Index of the highlighted entry
+    /// * `entry` - Returns the entry with the given index
+    pub fn print<F>(
+        &mut self,
+        frame: &mut Frame,
+        screen: (u16, u16),
+        footer_rows: u16,
+        total: usize,
+        current: usize,
+        entry: F,
+    ) where
+        F: Fn(usize) -> ListEntry,
+    {
+        let columns = cmp::max(screen.0, 1) as usize;
+        let available = screen
+            .1
+            .saturating_sub(frame.rows(screen.0))
+            .saturating_sub(footer_rows) as usize;
+        // The highlighted entry is shown even if it doesn't fit
+        let limit = cmp::max(cmp::min(self.limit, available), 1);
+        let (mut start_index, mut end_index) = calc_entries(current, total, limit);
+        let entries: Vec<ListEntry> = (start_index..end_index).map(entry).collect();
+        let first = start_index;
+        // Pointer, scroll arrow and the spaces around it, then the marker and a space
+        let rows = |entry: &ListEntry| {
+            let marker = entry
+                .marker
+                .as_ref()
+                .map_or(0, |m| text_width(m.content()) + 1);
+            let text = " ".repeat(4 + marker) + &entry.text;
+            wrap(&text, text.len(), columns).row + 1
+        };
+        // Entries that wrap take up more rows, so leave out the ones furthest from the highlighted one
+        while end_index - start_index > 1
+            && entries[start_index - first..end_index - first]
+                .iter()
+                .map(rows)
+                .sum::<usize>()
+                > available
+        {
+            if end_index - 1 > current {
+                end_index -= 1;
+            } else {
+                start_index += 1;
+            }
+        }
+        // Each entry starts a new line
+        self.first_line = frame.lines.len();
+        let shown = entries
+            .into_iter()
+            .enumerate()
+            .map(|(i, entry)| (first + i, entry))
+            .skip(start_index - first)
+            .take(end_index - start_index);
+        for (i, ListEntry { marker, text }) in shown {
+            let arrow = if i == start_index && start_index > 0 {
+                Figures::ArrowUp.as_str()
+            } else if i == end_index - 1 && end_index < total {
+                Figures::ArrowDown.as_str()
+            } else {
+                " "
+            };
+            frame.new_line();
+            frame.print_styled(if i == current {
+                style(Figures::Pointer.as_str()).with(Color::Cyan)
+            } else {
+                style(" ")
+            });
+            frame.print(format!(" {} ", arrow));
+            if let Some(marker) = marker {
+                frame.print_styled(marker);
+                frame.print(" ");
+            }
+            frame.print_styled(if i == current {
+                style(text).attribute(Attribute::Bold).with(Color::Cyan)
+            } else {
+                style(text)
+            });
+        }
+        self.start_index = start_index;
+        self.end_index = end_index;
+    }
+
+    /// Returns the index of the clicked entry, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - Line of the frame that was clicked, see `PromptCore::line_at`
+    pub fn clicked_entry(&self, line: usize) -> Option<usize> {
+        clicked_entry(line, self.first_line, self.start_index, self.end_index)
+    }
+}
+
+/// How quickly a second click must follow the first to count as a double click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(500);
+
+/// Detects double clicks on list entries
+#[derive(Debug, Default)]
+pub struct Clicks {
+    last: Option<(usize, Instant)>,
+}
+impl Clicks {
+    /// Registers a click on an entry, returns true if it completes a double click
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - Index of the clicked entry
+    pub fn click(&mut self, index: usize) -> bool {
+        let now = Instant::now();
+        let double = match self.last {
+            Some((last, at)) => last == index && now.duration_since(at) <= DOUBLE_CLICK_TIME,
+            None => false,
+        };
+        self.last = if double { None } else { Some((index, now)) };
+        double
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::testing::VirtualTerminal;
 
     #[test]
     #[cfg(windows)]
@@ -190,4 +1105,198 @@ mod tests {
         }
         assert_eq!(s, "↑↓←→◉◯✔✖…›─❯");
     }
+
+    #[test]
+    fn text_input_edits_grapheme_clusters() {
+        let mut input = TextInput::default();
+        for c in "ae\u{301}👩\u{200d}💻".chars() {
+            input.insert(c);
+        }
+        assert_eq!(input.graphemes(), 3);
+        input.backspace();
+        assert_eq!(input.as_str(), "ae\u{301}");
+        input.move_left();
+        assert_eq!(input.before_cursor(), "a");
+        input.insert('x');
+        input.delete();
+        assert_eq!(input.as_str(), "ax");
+        input.move_home();
+        input.move_left();
+        input.backspace();
+        assert_eq!(input.before_cursor(), "");
+        input.move_end();
+        input.move_right();
+        assert_eq!(input.before_cursor(), "ax");
+    }
+
+    #[test]
+    fn renderer_tracks_wrapped_lines() {
+        let terminal = VirtualTerminal::new(10, 10);
+        let mut output = terminal.output();
+        let mut renderer = Renderer::default();
+        let mut frame = Frame::new();
+        frame.print("a line that wraps twice");
+        frame.new_line();
+        frame.print_styled(style("multi\nline").with(Color::Red));
+        renderer.render(&mut output, &frame).unwrap();
+        assert_eq!(renderer.line_at(2), Some(0));
+        assert_eq!(renderer.line_at(3), Some(1));
+        assert_eq!(renderer.line_at(4), Some(2));
+        assert_eq!(renderer.line_at(5), None);
+
+        let mut frame = Frame::new();
+        frame.print("short");
+        frame.set_cursor();
+        frame.print(" and long");
+        renderer.render(&mut output, &frame).unwrap();
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "short and\nlong");
+        assert_eq!(screen.cursor, (5, 0));
+        assert_eq!(screen.cell(0, 4).style.foreground, None);
+    }
+
+    #[test]
+    fn renderer_writes_frame_at_once() {
+        #[derive(Clone, Default)]
+        struct Writes(Arc<Mutex<usize>>);
+        impl Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                *self.0.lock().unwrap() += 1;
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let writes = Writes::default();
+        let mut output = Output::new(writes.clone()).with_size(10, 10);
+        let mut renderer = Renderer::default();
+        let mut frame = Frame::new();
+        frame.print_styled(style("multi\nline").with(Color::Red));
+        frame.set_cursor();
+        frame.new_line();
+        frame.print("a line that wraps");
+        renderer.render(&mut output, &frame).unwrap();
+        assert_eq!(*writes.0.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn renderer_wraps_wide_characters() {
+        let terminal = VirtualTerminal::new(10, 10);
+        let mut output = terminal.output();
+        let mut renderer = Renderer::default();
+        let mut frame = Frame::new();
+        frame.print("1234567890ab");
+        renderer.render(&mut output, &frame).unwrap();
+
+        // The cell left blank in the last column must not keep the "0"
+        let mut frame = Frame::new();
+        frame.print("123456789");
+        frame.set_cursor();
+        frame.print("名名名名名");
+        assert_eq!(frame.rows(10), 2);
+        renderer.render(&mut output, &frame).unwrap();
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "123456789\n名名名名名");
+        assert_eq!(screen.cursor, (0, 1));
+        assert_eq!(renderer.line_at(1), Some(0));
+        assert_eq!(renderer.line_at(2), None);
+    }
+
+    #[test]
+    fn list_view_fits_wrapped_entries() {
+        let choices = ["a", "a much longer one", "c", "d"];
+        let mut list = ListView::new(10);
+        let mut frame = Frame::new();
+        frame.print("Pick one");
+        list.print(&mut frame, (10, 5), 0, choices.len(), 0, |i| ListEntry {
+            marker: None,
+            text: choices[i].to_string(),
+        });
+        assert_eq!(frame.rows(10), 5);
+        assert_eq!(list.clicked_entry(2), Some(1));
+        assert_eq!(list.clicked_entry(3), None);
+
+        let mut frame = Frame::new();
+        frame.print("Pick one");
+        list.print(&mut frame, (10, 5), 0, choices.len(), 3, |i| ListEntry {
+            marker: None,
+            text: choices[i].to_string(),
+        });
+        assert_eq!(frame.rows(10), 3);
+        assert_eq!(list.clicked_entry(1), Some(2));
+    }
+
+    #[test]
+    fn renderer_rewrites_changed_lines() {
+        #[derive(Clone, Default)]
+        struct Recorder(Arc<Mutex<Vec<u8>>>);
+        impl Write for Recorder {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let recorder = Recorder::default();
+        let terminal = VirtualTerminal::new(10, 10);
+        let mut output = terminal.output();
+        let mut recorded = Output::new(recorder.clone()).with_size(10, 10);
+        let mut renderer = Renderer::default();
+        let mut recorded_renderer = Renderer::default();
+        let mut render = |lines: &[&str]| {
+            let mut frame = Frame::new();
+            frame.print(lines.join("\n"));
+            renderer.render(&mut output, &frame).unwrap();
+            recorder.0.lock().unwrap().clear();
+            recorded_renderer.render(&mut recorded, &frame).unwrap();
+            String::from_utf8(recorder.0.lock().unwrap().clone()).unwrap()
+        };
+
+        render(&["first", "second", "third"]);
+        let written = render(&["first", "2nd", "third"]);
+        assert!(written.contains("2nd"));
+        assert!(!written.contains("first") && !written.contains("third"));
+        assert_eq!(terminal.screen().text(), "first\n2nd\nthird");
+
+        // A line that wraps pushes down everything below it
+        let written = render(&["first", "now wrapping", "third"]);
+        assert!(!written.contains("first") && written.contains("third"));
+        assert_eq!(terminal.screen().text(), "first\nnow wrappi\nng\nthird");
+
+        render(&["first", "and", "more", "lines"]);
+        assert_eq!(terminal.screen().text(), "first\nand\nmore\nlines");
+        render(&["fewer"]);
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "fewer");
+        assert_eq!(screen.cursor, (5, 0));
+
+        assert_eq!(render(&["fewer"]), "\u{1b}[6G");
+    }
+
+    #[test]
+    fn terminal_guard_restores_on_drop() {
+        let terminal = VirtualTerminal::new(20, 3);
+        let mut output = terminal.output();
+        queue!(
+            output,
+            cursor::Hide,
+            Print("? Name? › ab\n\rerror"),
+            cursor::MoveToPreviousLine(1),
+            cursor::MoveToColumn(13)
+        )
+        .unwrap();
+        // Built directly, as enabling raw mode needs a TTY
+        drop(TerminalGuard {
+            output,
+            finished: false,
+            mouse: false,
+        });
+
+        let screen = terminal.screen();
+        assert_eq!(screen.text(), "? Name? › ab");
+        assert_eq!(screen.cursor, (0, 1));
+        assert!(screen.cursor_visible);
+    }
 }